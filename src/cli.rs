@@ -1,245 +1,2903 @@
+use crate::cursor;
+use crate::minesweeper::{self, GameSettings, MinesweeperError, Tile};
 use crate::minesweeper::{GameEvent, GameState, Minesweeper, TileModifier, TileState};
-use crate::minesweeper::{GameSettings, Tile};
-use colored::{ColoredString, Colorize};
+use crate::screen::{self, AlternateScreen, Redraw, ResizeWatcher, Screen, Ticker, CLEAR};
+use crate::stats::{self, Stats};
+use clap::ValueEnum;
+use colored::{Color, ColoredString, Colorize};
+use crossterm::terminal;
+use serde::Serialize;
 use std::error::Error;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
 use std::time;
 
+#[derive(Clone)]
 pub enum Action {
     Sweep(usize, usize),
     Flag(usize, usize),
+    /// Flag several tiles at once, e.g. `f1,1 2,2 3,3`
+    FlagMany(Vec<(usize, usize)>),
     Question(usize, usize),
+    /// Repeat the last action which changed the board, shifted this many
+    /// columns to the right
+    RepeatLast(isize),
+    /// Pause or resume the game
+    Pause,
+    /// Several actions typed on one line, applied in order
+    Batch(Vec<Action>),
+    /// Print the moves made so far
+    History,
+    /// List every command
+    Help,
+    /// Describe a tile in words
+    Read(usize, usize),
+    /// Describe a whole row in words
+    ReadRow(usize),
+    /// Show the tiles a first sweep here would keep clear
+    Preview(usize, usize),
+    /// Pick out the swept tiles showing this number, until the next command
+    Highlight(TileState),
+    /// Make the obvious moves
+    Auto,
+    /// Say whether the rest of the board can be solved without guessing
+    Solvable,
+    /// Show another page of the board, on boards too big for the terminal.
+    /// Moves this many pages across and down.
+    Page(isize, isize),
     Quit,
+    /// Quit without asking for confirmation
+    ForceQuit,
 }
 
-impl Tile {
-    fn render(&self) -> ColoredString {
+#[derive(Debug)]
+pub enum InputError {
+    /// Nothing but whitespace was entered
+    Empty,
+    /// A tile outside of the board was given. Coordinates are one-based, as
+    /// typed.
+    OutOfRange {
+        input: String,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    /// The input couldn't be understood, with a message explaining why
+    Malformed(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Empty => write!(f, "please enter a command"),
+            InputError::OutOfRange {
+                input,
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let (name, value, max) = if (1..=*width).contains(x) {
+                    ("row", y, height)
+                } else {
+                    ("column", x, width)
+                };
+                write!(
+                    f,
+                    "Invalid Location: '{}': {} {} is outside 1-{}",
+                    input, name, value, max
+                )
+            }
+            InputError::Malformed(message) => write!(f, "{}", message),
+            InputError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for InputError {}
+
+impl From<io::Error> for InputError {
+    fn from(error: io::Error) -> Self {
+        InputError::Io(error)
+    }
+}
+
+impl Action {
+    /// The tile the action is applied to, or the last one for several tiles.
+    fn position(&self) -> Option<(usize, usize)> {
+        match *self {
+            Action::Sweep(x, y)
+            | Action::Flag(x, y)
+            | Action::Question(x, y)
+            | Action::Read(x, y)
+            | Action::Preview(x, y) => Some((x, y)),
+            Action::FlagMany(ref tiles) => tiles.last().copied(),
+            Action::Batch(ref actions) => actions.iter().rev().find_map(Action::position),
+            _ => None,
+        }
+    }
+
+    /// The same action applied to the tile `shift` columns to the right.
+    fn shifted(&self, shift: isize, width: usize) -> Result<Action, InputError> {
+        let shift_x = |x: usize| {
+            x.checked_add_signed(shift)
+                .filter(|&x| x < width)
+                .ok_or_else(|| {
+                    InputError::Malformed("Can't repeat past the edge of the board".into())
+                })
+        };
+        Ok(match *self {
+            Action::Sweep(x, y) => Action::Sweep(shift_x(x)?, y),
+            Action::Flag(x, y) => Action::Flag(shift_x(x)?, y),
+            Action::FlagMany(ref tiles) => Action::FlagMany(
+                tiles
+                    .iter()
+                    .map(|&(x, y)| Ok((shift_x(x)?, y)))
+                    .collect::<Result<_, InputError>>()?,
+            ),
+            Action::Question(x, y) => Action::Question(shift_x(x)?, y),
+            Action::Batch(ref actions) => Action::Batch(
+                actions
+                    .iter()
+                    .map(|action| action.shifted(shift, width))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => return Err(InputError::Malformed("Nothing to repeat".into())),
+        })
+    }
+}
+
+/// How tiles are drawn on the terminal, and described to screen readers.
+trait DrawTile {
+    fn render(&self, game_over: bool, detonated: bool, theme: Theme) -> ColoredString;
+    fn render_high_contrast(&self, game_over: bool, detonated: bool) -> ColoredString;
+    fn describe(&self) -> String;
+}
+
+/// Colors of the numbers from 1 to 8.
+const NUMBER_COLORS: [Color; 8] = [
+    Color::BrightBlue,
+    Color::Green,
+    Color::BrightRed,
+    Color::Blue,
+    Color::Red,
+    Color::Cyan,
+    Color::Black,
+    Color::Black,
+];
+
+/// Text and background colors of the numbers from 1 to 8 with `--theme
+/// high-contrast`, drawn in bold. No two numbers share a pair, and none of
+/// them share one with the other tiles.
+const HIGH_CONTRAST_NUMBER_COLORS: [(Color, Color); 8] = [
+    (Color::BrightWhite, Color::Blue),
+    (Color::Black, Color::Green),
+    (Color::BrightWhite, Color::Red),
+    (Color::BrightWhite, Color::Magenta),
+    (Color::Black, Color::Yellow),
+    (Color::Black, Color::Cyan),
+    (Color::Blue, Color::BrightWhite),
+    (Color::Red, Color::BrightWhite),
+];
+
+impl DrawTile for Tile {
+    /// Once the game is lost, `game_over` shows where the other mines were,
+    /// and `detonated` marks the mine which was swept.
+    fn render(&self, game_over: bool, detonated: bool, theme: Theme) -> ColoredString {
+        if theme == Theme::HighContrast {
+            return self.render_high_contrast(game_over, detonated);
+        }
         if let Some(modifier) = self.modifier {
             if modifier == TileModifier::Flagged {
                 return "F".bright_red();
             }
         }
+        if detonated {
+            return "X".white().on_red();
+        }
+        if game_over && self.is_mine() {
+            return "*".red();
+        }
         if !self.swept {
-            return "?".bright_black();
+            if self.modifier == Some(TileModifier::Unsure) {
+                return "?".magenta().bold();
+            }
+            // Not a `?`, so question marks show up without colors too
+            return "#".bright_black();
         }
 
-        match self.state {
-            TileState::Zero => " ".black(),
-            TileState::One => "1".bright_blue(),
-            TileState::Two => "2".green(),
-            TileState::Three => "3".bright_red(),
-            TileState::Four => "4".blue(),
-            TileState::Five => "5".red(),
-            TileState::Six => "6".cyan(),
-            TileState::Seven => "7".black(),
-            TileState::Eight => "8".black(),
-            TileState::Mine => "X".red(),
+        match self.state.adjacent_mines() {
+            None => "X".red(),
+            Some(0) => " ".black(),
+            Some(count) => count
+                .to_string()
+                .color(NUMBER_COLORS[usize::from(count) - 1]),
         }
     }
+    /// Like `render`, but every state has its own character, none of them
+    /// the classic ones, and its own text and background colors, so that the
+    /// board can be played without telling colors apart.
+    fn render_high_contrast(&self, game_over: bool, detonated: bool) -> ColoredString {
+        let mine = self.is_mine();
+        let tile = match (self.swept, self.modifier) {
+            _ if detonated => "%".bright_yellow().on_red(),
+            (_, Some(TileModifier::Flagged)) => "!".black().on_bright_red(),
+            (false, _) if game_over && mine => "@".bright_white().on_black(),
+            (false, Some(TileModifier::Unsure)) => "?".black().on_bright_yellow(),
+            (false, None) => "░".white().on_bright_black(),
+            (true, _) => match self.state.adjacent_mines() {
+                None => "%".bright_yellow().on_red(),
+                Some(0) => " ".black().on_bright_white(),
+                Some(count) => {
+                    let (text, background) = HIGH_CONTRAST_NUMBER_COLORS[usize::from(count) - 1];
+                    count.to_string().color(text).on_color(background)
+                }
+            },
+        };
+        tile.bold()
+    }
+    /// The tile in words, for screen readers.
+    fn describe(&self) -> String {
+        let description = match (self.swept, self.modifier, self.state) {
+            (_, Some(TileModifier::Flagged), _) => "flagged",
+            (false, Some(TileModifier::Unsure), _) => "hidden, marked with a question mark",
+            (false, _, _) => "hidden",
+            (true, _, TileState::Zero) => "empty",
+            (true, _, TileState::Mine) => "mine",
+            (true, _, state) => return state.to_string(),
+        };
+        description.to_string()
+    }
+}
+
+/// A hidden tile's chance of being a mine in tenths, from `⁰` to `⁹` or `!`
+/// where there certainly is one, on a background from green to red. The
+/// digits are raised so they can't be mistaken for numbers without colors.
+fn render_probability(probability: f64) -> ColoredString {
+    const TENTHS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    let tile = match probability {
+        p if p >= 1.0 => "!".to_string(),
+        p => TENTHS[((p * 10.0).round() as usize).min(9)].to_string(),
+    }
+    .black();
+    match probability {
+        p if p <= 0.0 => tile.on_bright_green(),
+        p if p < 0.25 => tile.on_green(),
+        p if p < 0.5 => tile.on_yellow(),
+        p if p < 1.0 => tile.on_bright_red(),
+        _ => tile.on_red(),
+    }
 }
 
+/// Starts the status line whenever the game has started.
+pub const TIME_LABEL: &str = "Time Elapsed = ";
+
 fn format_time(duration: time::Duration) -> String {
+    format!("{}{}", TIME_LABEL, format_clock(duration))
+}
+
+pub fn format_clock(duration: time::Duration) -> ColoredString {
     format!(
-        "Time Elapsed = {} │ ",
-        format!(
-            "{:0>2}:{:0>2}",
-            duration.as_secs() / 60,
-            duration.as_secs() % 60
-        )
-        .bright_yellow(),
+        "{:0>2}:{:0>2}",
+        duration.as_secs() / 60,
+        duration.as_secs() % 60
     )
+    .bright_yellow()
 }
 
-pub fn begin(start_settings: Option<GameSettings>) {
-    let settings = if let Some(s) = start_settings {
-        s
-    } else {
-        if let Ok(a) = get_params() {
-            a
-        } else {
-            GameSettings {
-                width: 30,
-                height: 16,
-                mines: 99,
-            }
+pub struct Options {
+    /// Print a JSON summary of the game once it ends
+    pub json: bool,
+    /// Label columns with letters instead of numbers
+    pub letters: bool,
+    /// Move a cursor around the board with the arrow keys instead of typing
+    /// coordinates
+    pub cursor: bool,
+    /// Accept mouse clicks in cursor mode
+    pub mouse: bool,
+    /// Don't clear the screen or show the list of commands
+    pub quiet: bool,
+    /// Seed for placing the mines
+    pub seed: Option<u64>,
+    /// Refuse to place more flags than there are mines
+    pub strict_flags: bool,
+    /// What coordinates typed without a command do
+    pub default_action: DefaultAction,
+    /// Whether to redraw the board in place or clear the screen first
+    pub redraw: Redraw,
+    /// Describe the board in words instead of drawing it
+    pub a11y: bool,
+    /// Lay the board out for a terminal this many characters wide
+    pub force_width: Option<usize>,
+    /// Apply the commands in this file (`-` for stdin) instead of asking for
+    /// them
+    pub script: Option<PathBuf>,
+    /// Don't warn about boards too big for the terminal
+    pub force_size: bool,
+    /// End the game after this long
+    pub timeout: Option<time::Duration>,
+    /// Terminal columns each tile takes up on the board
+    pub cell_width: usize,
+    /// Number the columns with a ruler instead of labels written downwards
+    pub compact: bool,
+    /// Mines which can be hit before the game is lost
+    pub lives: usize,
+    /// Let the first sweep hit a mine, for testing
+    pub allow_first_mine: bool,
+    /// Place the mines from the seed alone, whatever the first sweep
+    pub fixed_layout: bool,
+    /// Colors and characters to draw the tiles with
+    pub theme: Theme,
+    /// Which edges of the board the row and column labels are shown on
+    pub labels: Labels,
+    /// Draw each hidden tile's chance of being a mine in place of the tile
+    pub show_probabilities: bool,
+    /// Sweep this tile straight away
+    pub start: Option<StartPosition>,
+    /// The most tiles a board may have
+    pub max_board_size: usize,
+    /// Suggest a mine count for this difficulty when setting up a game
+    pub difficulty: Option<Difficulty>,
+    /// Ask before quitting a game which is under way
+    pub confirm_quit: bool,
+}
+
+/// The same settings as running the game with no arguments.
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            json: false,
+            letters: false,
+            cursor: false,
+            mouse: false,
+            quiet: false,
+            seed: None,
+            strict_flags: false,
+            default_action: DefaultAction::default(),
+            redraw: Redraw::default(),
+            a11y: false,
+            force_width: None,
+            script: None,
+            force_size: false,
+            timeout: None,
+            cell_width: 2,
+            compact: false,
+            lives: 1,
+            allow_first_mine: false,
+            fixed_layout: false,
+            theme: Theme::default(),
+            labels: Labels::default(),
+            show_probabilities: false,
+            start: None,
+            max_board_size: minesweeper::DEFAULT_MAX_TILES,
+            difficulty: None,
+            confirm_quit: true,
+        }
+    }
+}
+
+/// Colors and characters the tiles are drawn with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Theme {
+    #[default]
+    Classic,
+    /// Bold colors and a different character for every kind of tile, for low
+    /// vision or monochrome terminals
+    HighContrast,
+}
+
+/// Which edges of the board have row and column labels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Labels {
+    /// Column labels above the board and row labels to its left
+    #[default]
+    Standard,
+    /// Labels on every side, for following a row or column across big boards
+    All,
+}
+
+/// How hard a board should be, whatever its size.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// The classic board of this difficulty.
+    fn classic(self) -> GameSettings {
+        match self {
+            Difficulty::Easy => GameSettings::beginner(),
+            Difficulty::Medium => GameSettings::intermediate(),
+            Difficulty::Hard => GameSettings::expert(),
         }
+    }
+}
+
+/// A mine count for a `width` by `height` board which is about as hard as
+/// the classic board of the same difficulty. Long, thin boards get slightly
+/// fewer mines, as more of their tiles are on the edge where there are fewer
+/// neighbors to go by.
+pub fn suggest_mines(width: usize, height: usize, difficulty: Difficulty) -> usize {
+    // How many mines a board of this shape can take compared to a square one
+    let shape = |width: usize, height: usize| {
+        0.85 + 0.15 * (width.min(height) as f64 / width.max(height) as f64)
     };
-    let mut game = Minesweeper::new(&settings).unwrap();
+    // The classic board's own density, as if it were square, so that the
+    // classic board gets its classic count back
+    let classic = difficulty.classic();
+    let density = classic.mines as f64
+        / (classic.width * classic.height) as f64
+        / shape(classic.width, classic.height);
+    let tiles = width * height;
+    let mines = (tiles as f64 * density * shape(width, height)).round() as usize;
+    // At least the first tile swept has to be clear
+    mines.max(1).min(tiles.saturating_sub(1))
+}
 
-    render(&game);
+/// Where `--start` sweeps the first tile as the game begins.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartPosition {
+    Center,
+    /// The top left corner
+    Corner,
+    /// A given tile, zero-based
+    Tile(usize, usize),
+}
 
-    // Game Loop
-    loop {
-        while let Some(e) = game.events.next() {
-            match e {
-                GameEvent::SweepDone
-                | GameEvent::FlagTile(_, _, _)
-                | GameEvent::RevealMine(_, _, _) => {
-                    render(&game);
-                }
-                _ => (),
-            }
+impl StartPosition {
+    /// Reads `center`, `corner`, or a tile typed as in the game, like `3,5`.
+    pub fn parse(s: &str) -> Result<StartPosition, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "center" | "centre" => return Ok(StartPosition::Center),
+            "corner" => return Ok(StartPosition::Corner),
+            _ => (),
         }
-        if game.state == GameState::GameOver {
-            println!("{}", "Game Over!".red());
-            break;
+        match parse_coordinates(s) {
+            Ok((0, _) | (_, 0)) => Err("tiles are numbered from 1".into()),
+            Ok((x, y)) => Ok(StartPosition::Tile(x - 1, y - 1)),
+            Err(error) => Err(format!("expected center, corner or x,y: {}", error)),
         }
-        if game.state == GameState::Victory {
-            println!("{}", "You Win!".red());
-            break;
+    }
+    /// The tile to sweep on a `width` by `height` board. A given tile may be
+    /// off the board.
+    pub fn tile(self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            StartPosition::Center => ((width - 1) / 2, (height - 1) / 2),
+            StartPosition::Corner => (0, 0),
+            StartPosition::Tile(x, y) => (x, y),
         }
-        let result = take_input((game.board.width, game.board.height));
-        if let Ok(action) = result {
-            match action {
-                Action::Sweep(x, y) => {
-                    game.sweep(x, y);
-                }
-                Action::Flag(x, y) => {
-                    game.flag(x, y);
-                }
-                Action::Quit => {
-                    break;
-                }
+    }
+}
 
-                _ => (),
-            }
-        } else if let Err(error) = result {
-            println!("{}", error);
+/// The action for coordinates typed without a command in front.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum DefaultAction {
+    #[default]
+    Sweep,
+    Flag,
+}
+
+impl DefaultAction {
+    fn action(self, x: usize, y: usize) -> Action {
+        match self {
+            DefaultAction::Sweep => Action::Sweep(x, y),
+            DefaultAction::Flag => Action::Flag(x, y),
         }
     }
 }
 
-pub fn render(game: &Minesweeper) {
-    print!("{}c", 27 as char);
-    let y_max_len = (game.board.height + 1).to_string().len();
-    print!("{: ^1$}┃", "", y_max_len);
-    for (x, i) in (0..game.board.width).enumerate() {
-        print!("{}", x + 1);
-        if i < 9 && x != game.board.width - 1 {
-            print!(" ")
+/// Blank columns to the left and right of each tile, which take up
+/// `options.cell_width` columns with the tile in the middle.
+fn cell_padding(options: &Options) -> (usize, usize) {
+    let left = (options.cell_width - 1) / 2;
+    (left, options.cell_width - 1 - left)
+}
+
+/// Width of the row number column to the left of a board `height` rows tall.
+fn gutter_width(height: usize) -> usize {
+    (height + 1).to_string().len()
+}
+
+/// The label above a column, its number or with `--letters` its letters.
+fn column_label(x: usize, options: &Options) -> String {
+    if options.letters {
+        column_to_letters(x)
+    } else {
+        (x + 1).to_string()
+    }
+}
+
+/// Lines of column labels above a board `width` columns wide. Labels are
+/// written downwards, one character per line, so that every label fits in
+/// its cell.
+fn header_lines(width: usize, options: &Options) -> usize {
+    if uses_ruler(options) {
+        return 2;
+    }
+    column_label(width - 1, options).len()
+}
+
+/// Whether the columns are numbered with a [`ruler`]. Letters are still
+/// written downwards, they take up few lines even on huge boards.
+fn uses_ruler(options: &Options) -> bool {
+    options.compact && !options.letters
+}
+
+/// Column numbers for `--compact`: a tick every 5 columns, with the number
+/// above every tenth one ending over its column. Numbers which would run off
+/// the left of the page are left out. Each line has a character for every
+/// terminal column the tiles take up, as numbers can span wide cells.
+fn ruler(columns: Range<usize>, options: &Options) -> [Vec<char>; 2] {
+    let (left_padding, _) = cell_padding(options);
+    let mut numbers = vec![' '; columns.len() * options.cell_width];
+    let mut ticks = numbers.clone();
+    for (i, x) in columns.enumerate() {
+        let at = i * options.cell_width + left_padding;
+        if (x + 1) % 5 == 0 {
+            ticks[at] = '|';
+        }
+        if (x + 1) % 10 == 0 {
+            let label: Vec<char> = (x + 1).to_string().chars().collect();
+            if let Some(start) = (at + 1).checked_sub(label.len()) {
+                numbers[start..=at].copy_from_slice(&label);
+            }
         }
     }
-    if game.board.width < 9 {
-        print!(" ")
+    [numbers, ticks]
+}
+
+/// Maps a terminal cell (zero-based, relative to the top left of the frame
+/// drawn by `render`) to the board tile drawn there.
+pub fn board_position(
+    game: &Minesweeper,
+    options: &Options,
+    view: &View,
+    column: usize,
+    row: usize,
+) -> Option<(usize, usize)> {
+    // The header and separator bar come before the first board row, and the
+    // row numbers and a border character before the first board column.
+    let columns = visible_columns(game, options, view.page);
+    let rows = visible_rows(game, options, view.row_page);
+    let gutter = gutter_width(game.board.height);
+    let x = columns.start + column.checked_sub(gutter + 1)? / options.cell_width;
+    let y = rows.start + row.checked_sub(header_lines(game.board.width, options) + 1)?;
+    (columns.contains(&x) && rows.contains(&y)).then_some((x, y))
+}
+
+/// Splits a board `width` columns wide into pages of `per_page` columns.
+pub fn page_ranges(width: usize, per_page: usize) -> Vec<Range<usize>> {
+    let per_page = per_page.max(1);
+    (0..width)
+        .step_by(per_page)
+        .map(|start| start..width.min(start + per_page))
+        .collect()
+}
+
+/// How many columns of a `width` by `height` board fit across the terminal,
+/// or across `--force-width` characters. All of them if the width isn't
+/// known.
+fn columns_per_page(width: usize, height: usize, options: &Options) -> usize {
+    let terminal_width = options
+        .force_width
+        .or_else(|| terminal::size().ok().map(|(columns, _)| columns as usize));
+    // The row numbers and the borders either side take up the rest
+    let gutters = match options.labels {
+        Labels::Standard => gutter_width(height),
+        Labels::All => gutter_width(height) * 2,
+    };
+    match terminal_width {
+        Some(columns) => (columns.saturating_sub(gutters + 2) / options.cell_width).max(1),
+        None => width,
     }
-    println!("{}", "┃".white());
+}
 
-    let bar = format!(
-        "{0}{1:━>y_max_len$}{0}{2:━>3$}",
-        "━",
-        "╋",
-        "┫",
-        game.board.width * 2
-    )
-    .white();
-    println!("{}", bar);
+/// The columns shown on `page`, or on the last page if there are fewer.
+fn visible_columns(game: &Minesweeper, options: &Options, page: usize) -> Range<usize> {
+    let (width, height) = (game.board.width, game.board.height);
+    let pages = page_ranges(width, columns_per_page(width, height, options));
+    pages[page.min(pages.len() - 1)].clone()
+}
 
-    for y in 0..game.board.height {
-        let mut board_line = String::from("");
+/// How many rows of a `width` by `height` board fit on the terminal, leaving
+/// room for the column labels, the borders, the status line and a couple of
+/// lines for input and messages. All of them if the terminal's height isn't
+/// known.
+fn rows_per_page(width: usize, height: usize, options: &Options) -> usize {
+    match terminal::size() {
+        Ok((_, terminal_height)) => {
+            let labels = match options.labels {
+                Labels::Standard => header_lines(width, options),
+                Labels::All => header_lines(width, options) * 2,
+            };
+            let taken = labels + 6;
+            (terminal_height as usize).saturating_sub(taken).max(1)
+        }
+        Err(_) => height,
+    }
+}
 
-        for x in 0..(game.board.width) {
-            board_line.push_str(&game.board.tiles[x][y].render().to_string());
-            board_line.push_str(" ");
+/// The rows shown on `page`, or on the last page if there are fewer. Every
+/// row if there's no page.
+fn visible_rows(game: &Minesweeper, options: &Options, page: Option<usize>) -> Range<usize> {
+    match page {
+        Some(page) => {
+            let (width, height) = (game.board.width, game.board.height);
+            let pages = page_ranges(height, rows_per_page(width, height, options));
+            pages[page.min(pages.len() - 1)].clone()
         }
-        let line_num = format!("{: ^y_max_len$}", y + 1);
-        println!("{}{2}{}{2}", line_num, board_line.on_white(), "┃".white());
+        None => 0..game.board.height,
+    }
+}
+
+/// How many columns and rows of a `width` by `height` board can be drawn on
+/// the terminal at once.
+fn room_for(width: usize, height: usize, options: &Options) -> (usize, usize) {
+    (
+        columns_per_page(width, height, options),
+        rows_per_page(width, height, options),
+    )
+}
+
+/// Warns when a board won't fit on the terminal and asks whether to play it
+/// anyway, a page at a time. End of input counts as yes.
+fn confirm_size(settings: &GameSettings, options: &Options) -> bool {
+    let (columns, rows) = room_for(settings.width, settings.height, options);
+    if options.force_size || options.a11y || (columns >= settings.width && rows >= settings.height)
+    {
+        return true;
     }
     println!(
         "{}",
         format!(
-            "{2}{0:━>y_max_len$}{2}{1:━>dim$}",
-            "┻",
-            "┛",
-            "━",
-            dim = (game.board.width * 2)
+            "A {}x{} board won't fit on this terminal, which has room for {}x{}, so it will be shown a page at a time.",
+            settings.width, settings.height, columns, rows
         )
-        .white()
+        .yellow()
     );
-
-    if let Some(start_time) = game.start_time {
-        let elapsed = start_time.elapsed();
-        print!("{}", format_time(elapsed))
+    println!("Play it anyway? (y/n)");
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => true,
+        Ok(_) => parse_answer(&line) == Some(true),
     }
-    println!("Commands = x,y: sweep, fx,y: flag, q: quit");
 }
 
-pub fn get_params() -> Result<GameSettings, Box<dyn Error>> {
-    print!("{}c", 27 as char);
-    println!("{}", "Input options:".yellow().bold().underline());
-    let mut params: [usize; 3] = [16, 16, 40];
-    let default_msg = "Press Enter for default";
-    let param_name = ["Width:", "Height:", "Number of Mines:"];
-    for (i, _) in param_name.iter().enumerate() {
-        loop {
-            println!(
-                "{} ({}: {})",
-                param_name[i].yellow(),
-                default_msg,
-                params[i]
-            );
-            let mut line = String::new();
-            let _ = std::io::stdin().read_line(&mut line)?;
-            if line == String::from("\n") || line == String::from("\r\n") {
-                println!("{} {}", "default".italic(), params[i]);
-                break;
+pub fn begin(start_settings: Option<GameSettings>, options: Options) {
+    screen::restore_terminal_on_panic();
+    let start_settings_given = start_settings.is_some();
+    let mut settings = if let Some(s) = start_settings {
+        s
+    } else if options.script.is_some() {
+        // Scripts may be read from stdin, so don't prompt for settings
+        GameSettings::expert()
+    } else {
+        ask_settings(&options)
+    };
+    if settings.seed.is_none() {
+        settings.seed = options.seed;
+    }
+    // Settings typed in were already checked as they were entered
+    if start_settings_given && !options.quiet && !confirm_size(&settings, &options) {
+        return;
+    }
+    let mut game = new_game(&settings, &options);
+    loop {
+        play(&mut game, &options);
+        // Scripts and games set up for testing don't count
+        if options.script.is_none() && !options.allow_first_mine {
+            record_stats(&game);
+        }
+        // Scripts and games the player quit don't get another go
+        if options.script.is_some() || !game.is_over() {
+            break;
+        }
+        let Some(choice) = ask_menu_choice() else {
+            break;
+        };
+        // Seeded games carry on with new seeds, so their boards can be
+        // shared too
+        let new_seed = settings.seed.map(|_| rand::random());
+        match choice {
+            MenuChoice::Replay => game.reset_keep_mines(),
+            MenuChoice::NewBoard => {
+                settings.seed = new_seed;
+                game.reset(new_seed);
             }
-            if let Ok(a) = line.trim().parse::<usize>() {
-                if a == 0 {
-                    continue;
-                }
-                params[i] = a;
-                break;
-            };
+            MenuChoice::NewSettings => {
+                settings = ask_settings(&options);
+                settings.seed = new_seed;
+                game = new_game(&settings, &options);
+            }
+            MenuChoice::Quit => break,
         }
     }
-    let options = GameSettings {
-        width: params[0],
-        height: params[1],
-        mines: params[2],
+}
+
+/// Adds a finished game to the stats kept between sessions.
+fn record_stats(game: &Minesweeper) {
+    let Some(path) = stats::path() else {
+        return;
     };
-    Ok(options)
+    let mut stats = Stats::load(&path);
+    if stats.record(game) {
+        if let Err(error) = stats.save(&path) {
+            println!("{}", format!("Couldn't save the stats: {}", error).yellow());
+        }
+    }
 }
 
-pub fn take_input(dimensions: (usize, usize)) -> Result<Action, Box<dyn Error>> {
-    let mut line = String::new();
-    let _ = std::io::stdin().read_line(&mut line)?;
+/// Asks for the board size and mine count, falling back on the expert board.
+fn ask_settings(options: &Options) -> GameSettings {
+    if let Ok(a) = get_params(options) {
+        a
+    } else {
+        GameSettings::expert()
+    }
+}
+
+/// Sets up a game with these settings and the options the game itself
+/// keeps track of.
+fn new_game(settings: &GameSettings, options: &Options) -> Minesweeper {
+    let mut game = Minesweeper::with_max_tiles(settings, options.max_board_size)
+        .expect("settings are checked before playing");
+    game.strict_flags = options.strict_flags;
+    game.timeout = options.timeout;
+    game.lives = options.lives;
+    game.allow_first_mine = options.allow_first_mine;
+    game.fixed_layout = options.fixed_layout;
+    game
+}
 
-    let first_char = line.chars().next().unwrap();
+/// Plays one game to the end and prints how it went.
+fn play(game: &mut Minesweeper, options: &Options) {
+    // Quiet output is meant to be kept, so it goes on the main screen
+    let alternate_screen = match options.quiet {
+        true => None,
+        false => AlternateScreen::enter().ok(),
+    };
+    if let Some(start) = options.start {
+        let (x, y) = start.tile(game.board.width, game.board.height);
+        if let Err(error) = game.sweep(x, y) {
+            eprintln!("{}", error.to_string().red());
+        }
+    }
 
-    if first_char.is_alphabetic() {
-        line = line[1..].to_string();
+    if let Some(path) = &options.script {
+        if let Err(error) = play_script(game, path, options) {
+            eprintln!("{}", error.to_string().red());
+            std::process::exit(1);
+        }
+        render(game, &mut Screen::new(options), &View::default(), options);
+    } else if options.cursor {
+        if let Err(error) = cursor::play(game, options) {
+            println!("{}", error);
+        }
+    } else {
+        play_lines(game, options);
+    }
+    if let Some(alternate_screen) = alternate_screen {
+        // Leave the final board in the scrollback
+        drop(alternate_screen);
+        print!("{}", render_to_string(game, options, &View::default()));
     }
+    match game.state {
+        GameState::GameOver if game.time_up() => println!("{}", "Time's up!".red()),
+        GameState::GameOver => println!("{}", "Game Over!".red()),
+        GameState::Victory => println!("{}", "You Win!".red()),
+        _ => (),
+    }
+    if game.is_over() {
+        print_summary(game);
+    }
+    if options.json {
+        println!(
+            "{}",
+            serde_json::to_string(&GameSummary::new(game)).unwrap()
+        );
+    }
+}
 
-    let mut x = 0;
-    let mut y = 0;
-    for (i, a) in line.split(",").enumerate() {
-        let k = a.trim();
-        let coordinate: usize = if let Ok(a) = k.parse() { a } else { 1 };
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum GameResult {
+    Win,
+    Loss,
+    Unfinished,
+}
+
+/// Machine-readable overview of a game, printed with `--json`.
+#[derive(Serialize)]
+struct GameSummary {
+    result: GameResult,
+    elapsed_seconds: f64,
+    width: usize,
+    height: usize,
+    mines: usize,
+    flags: usize,
+    mines_left: isize,
+    revealed: usize,
+    /// The board as `GameBoard::to_ascii_grid` draws it, with the mines
+    /// shown once the game is over
+    board: String,
+    /// One-based coordinates of the flagged tiles, as they're typed
+    flagged: Vec<(usize, usize)>,
+    /// `GameBoard::fingerprint` in hex, once the mines are placed
+    fingerprint: Option<String>,
+}
 
-        if coordinate < 1 {
-            return Err("Invalid Location".into());
+impl GameSummary {
+    fn new(game: &Minesweeper) -> GameSummary {
+        let result = match game.state {
+            GameState::Victory => GameResult::Win,
+            GameState::GameOver => GameResult::Loss,
+            _ => GameResult::Unfinished,
+        };
+        GameSummary {
+            result,
+            elapsed_seconds: game.elapsed().as_secs_f64(),
+            width: game.board.width,
+            height: game.board.height,
+            mines: game.board.mines,
+            flags: game.board.flags,
+            mines_left: game.board.mines_left(),
+            revealed: game.board.swept_count(),
+            board: game.board.to_ascii_grid(!game.in_progress()),
+            flagged: game
+                .board
+                .flag_positions()
+                .into_iter()
+                .map(|(x, y)| (x + 1, y + 1))
+                .collect(),
+            fingerprint: game.fingerprint(),
         }
-        if i == 0 {
-            x = coordinate - 1
-        } else {
-            y = coordinate - 1
+    }
+}
+
+/// Statistics for a finished game: how long it took, and how efficiently
+/// it was played compared to the fewest clicks the board needs (its 3BV).
+fn print_summary(game: &Minesweeper) {
+    let seconds = game.elapsed().as_secs_f64();
+    let three_bv = game.board.three_bv();
+    println!("Time: {:.1}s", seconds);
+    let seed = game
+        .seed
+        .map_or(String::new(), |seed| format!(", seed {}", seed));
+    println!(
+        "Board: {}x{}, {} mines ({:.1}% density){}",
+        game.board.width,
+        game.board.height,
+        game.board.mines,
+        game.board.density() * 100.0,
+        seed
+    );
+    if let Some(fingerprint) = game.fingerprint() {
+        println!("Fingerprint: {}", fingerprint);
+    }
+    println!(
+        "3BV: {}, clicks: {}, 3BV/s: {:.2}",
+        three_bv,
+        game.moves,
+        three_bv as f64 / seconds.max(0.1)
+    );
+    if game.lost() {
+        println!("Cleared: {:.0}%", game.board.cleared_fraction() * 100.0);
+    }
+}
+
+/// What to do once a game is over.
+#[derive(Debug, PartialEq)]
+enum MenuChoice {
+    /// Play the same board again
+    Replay,
+    /// Play a different board of the same size
+    NewBoard,
+    /// Pick a new size and mine count
+    NewSettings,
+    Quit,
+}
+
+/// Reads a menu choice by its first letter or in full, case insensitively.
+fn parse_menu_choice(line: &str) -> Option<MenuChoice> {
+    match line.trim().to_ascii_lowercase().as_str() {
+        "r" | "replay" => Some(MenuChoice::Replay),
+        "n" | "new" => Some(MenuChoice::NewBoard),
+        "s" | "settings" => Some(MenuChoice::NewSettings),
+        "q" | "quit" => Some(MenuChoice::Quit),
+        _ => None,
+    }
+}
+
+/// Asks what to do next until a valid choice is made. Returns `None` at the
+/// end of input.
+fn ask_menu_choice() -> Option<MenuChoice> {
+    let choices = [
+        "r: replay this board",
+        "n: new board",
+        "s: new settings",
+        "q: quit",
+    ];
+    loop {
+        println!("{}", choices.join(", "));
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        match parse_menu_choice(&line) {
+            Some(choice) => return Some(choice),
+            None => println!("Unknown choice '{}'", line.trim()),
         }
     }
-    if y >= dimensions.1 || x >= dimensions.0 {
-        return Err("Invalid Location".into());
+}
+
+/// Game loop reading one typed command per line.
+fn play_lines(game: &mut Minesweeper, options: &Options) {
+    let mut screen = Screen::new(options);
+    // Frames printed one after another can be scrolled through instead
+    let mut view = match options.quiet {
+        true => View::default(),
+        false => View::paged(),
+    };
+    // A game started with --start is drawn below, along with its first sweep
+    if game.state == GameState::Empty {
+        render(game, &mut screen, &view, options);
     }
+    let mut last_action: Option<Action> = None;
+    let mut applied: Option<Action> = None;
 
-    let action: Action = if first_char.is_alphabetic() {
-        match first_char {
-            'f' => Action::Flag(x, y),
-            '?' => Action::Question(x, y),
-            'q' => Action::Quit,
-            _ => Action::Sweep(x, y),
+    loop {
+        let mut changed = false;
+        let mut redraw = false;
+        let mut rejections = Vec::new();
+        let mut announced = Vec::new();
+        while let Some(e) = game.events.next() {
+            changed |= !matches!(e, GameEvent::Rejected { .. });
+            match e {
+                // The end of the game is always drawn, whatever ended it
+                GameEvent::SweepDone
+                | GameEvent::GameEnd(_)
+                | GameEvent::FlagAllMines
+                | GameEvent::TimeUp
+                | GameEvent::FlagTile(_, _, _)
+                | GameEvent::QuestionTile(_, _, _)
+                | GameEvent::RevealMine(_, _, _)
+                | GameEvent::Pause
+                | GameEvent::Resume => redraw = true,
+                GameEvent::Rejected { x, y, reason } => rejections.push((x, y, reason)),
+                _ => (),
+            }
+            if options.a11y {
+                announced.push(e);
+            }
+        }
+        if let Some(announcement) = describe_events(&announced) {
+            println!("{}", announcement);
+        }
+        if redraw {
+            render(game, &mut screen, &view, options);
+        }
+        for (x, y, reason) in rejections {
+            println!("{},{}: {}", x + 1, y + 1, reason);
+        }
+        // Only actions which had an effect are worth repeating
+        if let (true, Some(action)) = (changed, applied.take()) {
+            last_action = Some(action);
+        }
+        if game.is_over() {
+            break;
+        }
+        let input = wait_for_input(game, &mut screen, &view, options);
+        // Whatever was typed once the time ran out is too late
+        if game.check_timeout() {
+            continue;
+        }
+        let result = input.and_then(|action| match (action, &last_action) {
+            (Action::RepeatLast(shift), Some(last)) => last.shifted(shift, game.board.width),
+            (Action::RepeatLast(_), None) => Err(InputError::Malformed("Nothing to repeat".into())),
+            (action, _) => Ok(action),
+        });
+        // Previews and highlights only last until the next command
+        view.preview.clear();
+        view.highlight = None;
+        match result {
+            Ok(Action::Preview(x, y)) if game.state == GameState::Empty => {
+                view.preview = game.board.safe_zone(x, y);
+                render(game, &mut screen, &view, options);
+            }
+            Ok(Action::Preview(_, _)) => println!("The game has already started"),
+            Ok(Action::Highlight(state)) => {
+                view.highlight = Some(state);
+                render(game, &mut screen, &view, options);
+            }
+            Ok(Action::Page(columns, rows)) => {
+                view.scroll(game, options, columns, rows);
+                render(game, &mut screen, &view, options);
+            }
+            Ok(action) => {
+                // Keep the tile being played in view
+                let position = action.position();
+                if let Some(position) = position.filter(|&(x, y)| game.board.in_bounds(x, y)) {
+                    view.follow(game, options, position);
+                }
+                applied = Some(action.clone());
+                match apply(game, action, options) {
+                    Ok(true) => (),
+                    Ok(false) => break,
+                    Err(error) => println!("{}", error),
+                }
+            }
+            Err(error) => println!("{}", error),
         }
+    }
+}
+
+/// Applies each line of a script in turn, without drawing the board. Stops at
+/// the first invalid line, or once the game is over.
+fn play_script(
+    game: &mut Minesweeper,
+    path: &Path,
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let script = if path == Path::new("-") {
+        io::read_to_string(io::stdin())?
     } else {
-        Action::Sweep(x, y)
+        fs::read_to_string(path)?
     };
+    let mut last_action: Option<Action> = None;
 
-    Ok(action)
+    for (number, line) in script.lines().enumerate() {
+        game.check_timeout();
+        if game.is_over() {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let action = match parse_input(
+            line,
+            (game.board.width, game.board.height),
+            options.default_action,
+            options.letters,
+        ) {
+            Ok(Action::RepeatLast(shift)) => match &last_action {
+                Some(last) => last.shifted(shift, game.board.width),
+                None => Err(InputError::Malformed("Nothing to repeat".into())),
+            },
+            result => result,
+        }
+        .map_err(|error| format!("line {}: {}", number + 1, error))?;
+        // Nobody is there to confirm quitting
+        if let Action::Quit | Action::ForceQuit = action {
+            break;
+        }
+        last_action = Some(action.clone());
+        apply(game, action, options).map_err(|error| format!("line {}: {}", number + 1, error))?;
+        while game.events.next().is_some() {}
+    }
+    Ok(())
+}
+
+/// Applies an action to the game. Returns false if the player quit, or an
+/// error naming the coordinate which is out of range for a move off the
+/// board.
+fn apply(game: &mut Minesweeper, action: Action, options: &Options) -> Result<bool, InputError> {
+    let dimensions = (game.board.width, game.board.height);
+    let off_board = |error| off_board(error, dimensions, options);
+    match action {
+        Action::Sweep(x, y) => game.sweep(x, y).map_err(off_board)?,
+        Action::Flag(x, y) => game.flag(x, y).map_err(off_board)?,
+        Action::FlagMany(tiles) => {
+            // Check every tile before flagging any of them
+            if let Some(&(x, y)) = tiles.iter().find(|&&(x, y)| !game.board.in_bounds(x, y)) {
+                return Err(off_board(MinesweeperError::OutOfBounds { x, y }));
+            }
+            for (x, y) in tiles {
+                game.flag(x, y).map_err(off_board)?;
+            }
+        }
+        Action::Question(x, y) => game.question(x, y).map_err(off_board)?,
+        Action::Pause => toggle_pause(game),
+        Action::History => print_history(game),
+        Action::Help => print!("{}", HELP),
+        Action::Auto => {
+            if game.auto_step() == 0 {
+                println!("No obvious moves left");
+            }
+        }
+        Action::Read(x, y) => {
+            if let Some(tile) = game.board.get(x, y) {
+                let column = column_label(x, options);
+                println!("row {} column {}: {}", y + 1, column, tile.describe());
+            }
+        }
+        Action::Solvable => match (game.state == GameState::Empty, game.is_solvable_from_here()) {
+            (true, _) => println!("Sweep a tile to start the game first"),
+            (false, true) => println!("Yes, the rest of the board can be solved without guessing"),
+            (false, false) => println!("No, the rest of the board needs a guess"),
+        },
+        Action::ReadRow(y) => println!("{}", describe_row(game, y, options)),
+        Action::Batch(actions) => {
+            for action in actions {
+                if game.is_over() {
+                    break;
+                }
+                if !apply(game, action, options)? {
+                    return Ok(false);
+                }
+            }
+        }
+        // Only makes sense typed on its own, see `play_lines`
+        Action::RepeatLast(_)
+        | Action::Page(_, _)
+        | Action::Preview(_, _)
+        | Action::Highlight(_) => (),
+        Action::Quit => {
+            let ask = game.in_progress() && game.board.swept_count() > 0 && options.confirm_quit;
+            return Ok(ask && !confirm_quit());
+        }
+        Action::ForceQuit => return Ok(false),
+    }
+    Ok(true)
 }
 
+/// The game's error for a move off the board as the one for a tile typed off
+/// the board, with the tile written as it would be typed.
+fn off_board(error: MinesweeperError, dimensions: (usize, usize), options: &Options) -> InputError {
+    let MinesweeperError::OutOfBounds { x, y } = error else {
+        return InputError::Malformed(error.to_string());
+    };
+    let separator = if options.letters { "" } else { "," };
+    let (width, height) = dimensions;
+    InputError::OutOfRange {
+        input: format!(
+            "{}{}{}",
+            column_label(x, options),
+            separator,
+            y.saturating_add(1)
+        ),
+        x: x.saturating_add(1),
+        y: y.saturating_add(1),
+        width,
+        height,
+    }
+}
 
+/// Asks whether to abandon the current game. Only a yes quits, apart from
+/// the end of input.
+fn confirm_quit() -> bool {
+    println!("Really quit? (y/N)");
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => true,
+        Ok(_) => parse_answer(&line) == Some(true),
+    }
+}
+
+/// Reads a yes or no answer, `None` if it's neither.
+fn parse_answer(line: &str) -> Option<bool> {
+    match line.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Some(true),
+        "n" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Keeps the clock below the board running while waiting for input. Not
+/// done for output meant to be read afterwards, like `--json`.
+pub fn start_ticker(game: &Minesweeper, screen: &Screen, options: &Options) -> Option<Ticker> {
+    screen.ticker(clock_start(game, options)?)
+}
+
+/// When the game would have started had it never been paused, for the clock
+/// to count from. `None` if the clock shouldn't be kept running.
+fn clock_start(game: &Minesweeper, options: &Options) -> Option<time::Instant> {
+    if !game.clock_running() || options.json {
+        return None;
+    }
+    time::Instant::now().checked_sub(game.elapsed())
+}
+
+const HELP: &str = "\
+Commands
+
+  x,y                  sweep, or flag with --default-action flag
+  c5, 5c               a tile by column letter, with --letters
+  sx,y                 sweep
+  fx,y                 flag, or take a flag away
+  ?x,y                 mark with a question mark
+  f1,1 2,2 s3,3        several tiles or commands on one line
+  ., .+, .-            repeat the last command, or one column right / left
+  :a                   make the obvious moves
+  :h                   list the moves made so far
+  p                    pause / resume
+  <, >, ^, v           show another page of a big board
+  read x,y, peek x,y   describe a tile without touching it
+  row n                describe a whole row
+  preview x,y          show what a first sweep here keeps clear
+  highlight n          pick out the tiles showing n
+  solvable?            whether the rest can be solved without guessing
+  help, ?              show this list
+  q                    quit, asking first if a game is under way
+  q!                   quit without asking
+";
+
+fn print_history(game: &Minesweeper) {
+    if game.history.is_empty() {
+        println!("No moves yet");
+    }
+    for (i, entry) in game.history.iter().enumerate() {
+        println!("{:>4}. {}: {}", i + 1, entry.action, entry.outcome);
+    }
+}
+
+pub fn toggle_pause(game: &mut Minesweeper) {
+    if game.state == GameState::Paused {
+        game.resume();
+    } else {
+        game.pause();
+    }
+}
+
+pub fn render(game: &Minesweeper, screen: &mut Screen, view: &View, options: &Options) {
+    let frame = render_to_string(game, options, view);
+    print!("{}", screen.draw(&frame));
+}
+
+/// What is shown besides the board itself.
+#[derive(Default)]
+pub struct View {
+    /// The tile highlighted in cursor mode
+    pub cursor: Option<(usize, usize)>,
+    /// Which columns of a board too wide for the terminal are shown
+    pub page: usize,
+    /// Which rows of a board too tall for the terminal are shown. Every row
+    /// is shown if `None`, as when the board is left in the scrollback.
+    pub row_page: Option<usize>,
+    /// Tiles which would be kept clear of mines by the first sweep
+    pub preview: Vec<(usize, usize)>,
+    /// Swept tiles with this number stand out, and the rest are dimmed
+    pub highlight: Option<TileState>,
+}
+
+impl View {
+    /// A view showing one page of the board at a time, starting at the top
+    /// left.
+    pub fn paged() -> View {
+        View {
+            row_page: Some(0),
+            ..View::default()
+        }
+    }
+
+    /// Shows the pages tile `(x, y)` is on.
+    pub fn follow(&mut self, game: &Minesweeper, options: &Options, (x, y): (usize, usize)) {
+        let (columns, rows) = room_for(game.board.width, game.board.height, options);
+        self.page = x / columns;
+        if self.row_page.is_some() {
+            self.row_page = Some(y / rows);
+        }
+    }
+
+    /// Moves the given number of pages across and down, stopping at the
+    /// edges of the board.
+    fn scroll(&mut self, game: &Minesweeper, options: &Options, columns: isize, rows: isize) {
+        let last_page = |length, per_page| page_ranges(length, per_page).len() - 1;
+        let (columns_per_page, rows_per_page) =
+            room_for(game.board.width, game.board.height, options);
+        let last_column_page = last_page(game.board.width, columns_per_page);
+        self.page = self
+            .page
+            .saturating_add_signed(columns)
+            .min(last_column_page);
+        if let Some(row_page) = self.row_page {
+            let last_row_page = last_page(game.board.height, rows_per_page);
+            self.row_page = Some(row_page.saturating_add_signed(rows).min(last_row_page));
+        }
+    }
+}
+
+/// Draws the whole frame. Boards too wide for the terminal are drawn a page
+/// of columns at a time.
+pub fn render_to_string(game: &Minesweeper, options: &Options, view: &View) -> String {
+    if options.a11y {
+        return describe_board(game);
+    }
+    let mut out = String::new();
+    let y_max_len = gutter_width(game.board.height);
+    let columns = visible_columns(game, options, view.page);
+    let rows = visible_rows(game, options, view.row_page);
+    // Without a cursor, the tile of the last move is highlighted instead
+    let last_move = match view.cursor {
+        Some(_) => None,
+        None => game.history.back().map(|entry| entry.action.position()),
+    };
+    let header_lines = header_lines(game.board.width, options);
+    // The border turns red or green once the game is lost or won
+    let border = |s: &str| match game.state {
+        GameState::GameOver => s.red(),
+        GameState::Victory => s.green(),
+        _ => s.white(),
+    };
+    let (left_padding, right_padding) = cell_padding(options);
+    let ruler = uses_ruler(options).then(|| ruler(columns.clone(), options));
+    let mut header = String::new();
+    for line in 0..header_lines {
+        write!(header, "{: ^1$}┃", "", y_max_len).unwrap();
+        for (i, x) in columns.clone().enumerate() {
+            // The padding either side of the label, and the label itself
+            let (left, c, right): (String, char, String) = match &ruler {
+                Some(ruler) => {
+                    let cell = &ruler[line][i * options.cell_width..][..options.cell_width];
+                    (
+                        cell[..left_padding].iter().collect(),
+                        cell[left_padding],
+                        cell[left_padding + 1..].iter().collect(),
+                    )
+                }
+                None => {
+                    // Shorter labels are aligned to the bottom line
+                    let label = column_label(x, options);
+                    let c = (line + label.len())
+                        .checked_sub(header_lines)
+                        .map_or(' ', |i| label.as_bytes()[i] as char);
+                    (" ".repeat(left_padding), c, " ".repeat(right_padding))
+                }
+            };
+            header.push_str(&left);
+            if last_move.map(|(last_x, _)| last_x) == Some(x) {
+                write!(header, "{}", c.to_string().underline()).unwrap();
+            } else {
+                header.push(c);
+            }
+            header.push_str(&right);
+        }
+        writeln!(header, "{}", border("┃")).unwrap();
+    }
+    out.push_str(&header);
+
+    // Arrows on the borders show where more of the board is off screen
+    let more_above = if rows.start > 0 { "▲" } else { "━" };
+    let more_below = if rows.end < game.board.height {
+        "▼"
+    } else {
+        "━"
+    };
+    let more_left = if columns.start > 0 { "◀" } else { "┃" };
+    let more_right = if columns.end < game.board.width {
+        "▶"
+    } else {
+        "┃"
+    };
+    let bar = format!(
+        "{0}{1:━>y_max_len$}{2}{3:━>4$}",
+        "━",
+        "╋",
+        more_above,
+        "┫",
+        columns.len() * options.cell_width
+    );
+    writeln!(out, "{}", border(&bar)).unwrap();
+
+    let probabilities = options
+        .show_probabilities
+        .then(|| game.mine_probabilities());
+    for y in rows.clone() {
+        let mut board_line = String::from("");
+
+        for x in columns.clone() {
+            let Some(&(mut board_tile)) = game.board.get(x, y) else {
+                continue;
+            };
+            // Winning flags the mines left, as the classic game does
+            if game.won() && board_tile.is_mine() {
+                board_tile.modifier = Some(TileModifier::Flagged);
+            }
+            write!(board_line, "{: <1$}", "", left_padding).unwrap();
+            let probability = probabilities.as_ref().and_then(|p| p[x][y]);
+            let mut tile = match (probability, board_tile.modifier) {
+                // Flags and question marks are the player's own, and stay
+                (Some(probability), None) => render_probability(probability),
+                _ => board_tile.render(game.lost(), game.detonated == Some((x, y)), options.theme),
+            };
+            if let Some(state) = view.highlight {
+                tile = match board_tile.swept && board_tile.state == state {
+                    true => tile.reversed(),
+                    false => tile.dimmed(),
+                };
+            }
+            if view.cursor == Some((x, y)) {
+                board_line.push_str(&tile.on_yellow().to_string());
+            } else if view.preview.contains(&(x, y)) {
+                board_line.push_str(&tile.on_bright_green().to_string());
+            } else if last_move == Some((x, y)) {
+                board_line.push_str(&tile.on_bright_cyan().to_string());
+            } else {
+                board_line.push_str(&tile.to_string());
+            }
+            write!(board_line, "{: <1$}", "", right_padding).unwrap();
+        }
+        let mut line_num = format!("{: ^y_max_len$}", y + 1).normal();
+        if last_move.map(|(_, last_y)| last_y) == Some(y) {
+            line_num = line_num.underline();
+        }
+        write!(
+            out,
+            "{}{}{}{}",
+            line_num,
+            border(more_left),
+            board_line.on_white(),
+            border(more_right)
+        )
+        .unwrap();
+        if options.labels == Labels::All {
+            write!(out, "{}", line_num).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+    // With labels below, the bottom border joins onto them like the top one
+    let corners = match options.labels {
+        Labels::Standard => ("┻", "┛"),
+        Labels::All => ("╋", "┫"),
+    };
+    writeln!(
+        out,
+        "{}",
+        border(&format!(
+            "{2}{0:━>y_max_len$}{3}{1:━>dim$}",
+            corners.0,
+            corners.1,
+            "━",
+            more_below,
+            dim = (columns.len() * options.cell_width)
+        ))
+    )
+    .unwrap();
+    if options.labels == Labels::All {
+        out.push_str(&header);
+    }
+
+    let mut status = Vec::new();
+    if game.state != GameState::Empty {
+        status.push(format_time(game.elapsed()));
+    }
+    let mines_left = game.board.mines_left();
+    status.push(format!(
+        "Mines Left = {}",
+        if mines_left < 0 {
+            mines_left.to_string().bright_red()
+        } else {
+            mines_left.to_string().bright_yellow()
+        }
+    ));
+    status.push(format!(
+        "Flags = {}",
+        game.board.flags.to_string().bright_yellow()
+    ));
+    status.push(format!(
+        "Cleared = {}",
+        format!("{:.0}%", game.board.cleared_fraction() * 100.0).bright_yellow()
+    ));
+    if options.lives > 1 {
+        status.push(format!(
+            "Lives = {}",
+            game.lives.to_string().bright_yellow()
+        ));
+    }
+    if game.state == GameState::Paused {
+        status.push("Paused".bright_yellow().bold().to_string());
+    } else if let (false, DefaultAction::Flag) = (options.cursor, options.default_action) {
+        status.push("Flag mode".bright_red().bold().to_string());
+    }
+    if let Some(code) = game.code() {
+        status.push(format!("Board code = {}", code));
+    }
+    if columns.len() < game.board.width {
+        let hint = if options.cursor {
+            ""
+        } else {
+            ", < and > for more"
+        };
+        status.push(format!(
+            "Columns {}-{} of {}{}",
+            columns.start + 1,
+            columns.end,
+            game.board.width,
+            hint
+        ));
+    }
+    if rows.len() < game.board.height {
+        let hint = if options.cursor {
+            ""
+        } else {
+            ", ^ and v for more"
+        };
+        status.push(format!(
+            "Rows {}-{} of {}{}",
+            rows.start + 1,
+            rows.end,
+            game.board.height,
+            hint
+        ));
+    }
+    if !options.quiet {
+        let commands = match (options.cursor, options.default_action) {
+            (true, _) => "Commands = arrows/hjkl: move, space: sweep, f: flag, c: chord, p: pause, ?: help, q: quit",
+            (false, DefaultAction::Sweep) => "Commands = x,y: sweep, fx,y: flag, ?x,y: mark, .: repeat, p: pause, help: more, q: quit",
+            (false, DefaultAction::Flag) => "Commands = x,y: flag, sx,y: sweep, ?x,y: mark, .: repeat, p: pause, help: more, q: quit",
+        };
+        status.push(commands.to_string());
+    }
+    if !status.is_empty() {
+        writeln!(out, "{}", status.join(" │ ")).unwrap();
+    }
+    out
+}
+
+/// What a move did in a sentence or two, from the events it caused, for
+/// screen readers. `events` are oldest first.
+fn describe_events(events: &[GameEvent]) -> Option<String> {
+    let mut sentences = Vec::new();
+    // Tiles opened around a zero, as opposed to the one swept
+    let mut cascade: Option<Vec<(usize, usize)>> = None;
+    for event in events {
+        match *event {
+            GameEvent::RevealTile(x, y, _) if cascade.is_some() => {
+                cascade.as_mut().unwrap().push((x, y))
+            }
+            GameEvent::RevealTile(x, y, tile) => {
+                let what = match tile.state {
+                    TileState::Zero => "empty".to_string(),
+                    TileState::Mine => "a mine".to_string(),
+                    state => format!("a {}", state),
+                };
+                sentences.push(format!("Swept {},{}: it is {}.", x + 1, y + 1, what));
+            }
+            GameEvent::SweepBegin => cascade = Some(Vec::new()),
+            GameEvent::SweepDone => {
+                let tiles = cascade.take().unwrap_or_default();
+                if !tiles.is_empty() {
+                    let (xs, ys): (Vec<usize>, Vec<usize>) = tiles.iter().copied().unzip();
+                    sentences.push(format!(
+                        "Cascade opened {} tiles from {},{} to {},{}.",
+                        tiles.len(),
+                        xs.iter().min().unwrap() + 1,
+                        ys.iter().min().unwrap() + 1,
+                        xs.iter().max().unwrap() + 1,
+                        ys.iter().max().unwrap() + 1
+                    ));
+                }
+            }
+            GameEvent::FlagTile(x, y, tile) => {
+                let what = if tile.modifier.is_some() {
+                    "Flagged"
+                } else {
+                    "Unflagged"
+                };
+                sentences.push(format!("{} {},{}.", what, x + 1, y + 1));
+            }
+            GameEvent::QuestionTile(x, y, tile) => {
+                let what = if tile.modifier.is_some() {
+                    "Marked"
+                } else {
+                    "Unmarked"
+                };
+                sentences.push(format!("{} {},{}.", what, x + 1, y + 1));
+            }
+            GameEvent::LifeLost(1) => sentences.push("1 life left.".to_string()),
+            GameEvent::LifeLost(lives) => sentences.push(format!("{} lives left.", lives)),
+            GameEvent::Pause => sentences.push("Paused.".to_string()),
+            GameEvent::Resume => sentences.push("Resumed.".to_string()),
+            _ => (),
+        }
+    }
+    (!sentences.is_empty()).then(|| sentences.join(" "))
+}
+
+/// Every tile of row `y` in words, with runs of the same kind of tile
+/// described together.
+fn describe_row(game: &Minesweeper, y: usize, options: &Options) -> String {
+    let mut runs: Vec<(usize, usize, String)> = Vec::new();
+    let tiles = (0..game.board.width).filter_map(|x| Some((x, game.board.get(x, y)?)));
+    for (x, tile) in tiles {
+        let description = tile.describe();
+        match runs.last_mut() {
+            Some((_, end, last)) if *last == description => *end = x,
+            _ => runs.push((x, x, description)),
+        }
+    }
+    let runs: Vec<String> = runs
+        .into_iter()
+        .map(|(start, end, description)| match start == end {
+            true => format!("column {} is {}", column_label(start, options), description),
+            false => format!(
+                "columns {} to {} are {}",
+                column_label(start, options),
+                column_label(end, options),
+                description
+            ),
+        })
+        .collect();
+    format!("row {}: {}", y + 1, runs.join(", "))
+}
+
+/// The state of the game in a sentence or two instead of the grid, for screen
+/// readers. What each move did is described by `describe_events`, and single
+/// tiles and rows with the `read` and `row` commands.
+fn describe_board(game: &Minesweeper) -> String {
+    let mut sentences = Vec::new();
+    match game.state {
+        // Mines aren't placed until the first sweep
+        GameState::Empty => sentences.push("Sweep a tile to start.".to_string()),
+        GameState::GameOver if game.time_up() => sentences.push("Time's up.".to_string()),
+        GameState::GameOver => sentences.push("You hit a mine.".to_string()),
+        GameState::Victory => sentences.push("Every mine is found.".to_string()),
+        GameState::Playing | GameState::Paused => {
+            sentences.push(format!(
+                "{} mines left, {} flags placed, {} safe tiles left to sweep.",
+                game.board.mines_left(),
+                game.board.flags,
+                game.board.unswept_safe_count()
+            ));
+            if game.state == GameState::Paused {
+                sentences.push("Paused.".to_string());
+            }
+        }
+    }
+    sentences.join(" ") + "\n"
+}
+
+pub fn get_params(options: &Options) -> Result<GameSettings, Box<dyn Error>> {
+    if !options.quiet {
+        print!("{}", CLEAR);
+    }
+    println!("{}", "Input options:".yellow().bold().underline());
+    // The classic board of the chosen difficulty is the default
+    let default = options
+        .difficulty
+        .map_or(GameSettings::intermediate(), Difficulty::classic);
+    let mut params: [usize; 3] = [default.width, default.height, default.mines];
+    let default_msg = "Press Enter for default";
+    let param_name = ["Width:", "Height:", "Number of Mines:"];
+    for (i, _) in param_name.iter().enumerate() {
+        if let (2, Some(difficulty)) = (i, options.difficulty) {
+            params[2] = suggest_mines(params[0], params[1], difficulty);
+        }
+        loop {
+            println!(
+                "{} ({}: {})",
+                param_name[i].yellow(),
+                default_msg,
+                params[i]
+            );
+            let mut line = String::new();
+            if std::io::stdin().read_line(&mut line)? == 0 {
+                return Err("No options given".into());
+            }
+            let previous = params[i];
+            if line == "\n" || line == "\r\n" {
+                println!("{} {}", "default".italic(), params[i]);
+            } else if let Ok(a) = line.trim().parse::<usize>() {
+                if a == 0 {
+                    continue;
+                }
+                params[i] = a;
+            } else {
+                continue;
+            }
+            if i < 2 {
+                // The height isn't known yet when the width is checked
+                let height = if i == 0 { 1 } else { params[1] };
+                let size = GameSettings::builder()
+                    .width(params[0])
+                    .height(height)
+                    .max_tiles(options.max_board_size)
+                    .build();
+                if let Err(error) = size {
+                    println!("{}", error.to_string().red());
+                    params[i] = previous;
+                    continue;
+                }
+            }
+            if i < 2 && !options.force_size && !options.a11y {
+                // The height isn't known yet when the width is checked, so
+                // the default is assumed
+                let (columns, rows) = room_for(params[0], params[1], options);
+                let (name, room) = [("wide", columns), ("tall", rows)][i];
+                if params[i] > room {
+                    println!(
+                        "{}",
+                        format!(
+                            "Boards up to {} {} fit on this terminal, larger ones are shown a page at a time.",
+                            room, name
+                        )
+                        .yellow()
+                    );
+                    println!("Keep {}? (y/n)", params[i]);
+                    let mut line = String::new();
+                    io::stdin().read_line(&mut line)?;
+                    if parse_answer(&line) == Some(false) {
+                        params[i] = previous;
+                        continue;
+                    }
+                }
+            }
+            if i == 2 {
+                let settings = GameSettings::builder()
+                    .width(params[0])
+                    .height(params[1])
+                    .mines(params[2])
+                    .max_tiles(options.max_board_size)
+                    .build();
+                let game = settings.and_then(|settings| {
+                    Minesweeper::with_max_tiles(&settings, options.max_board_size)
+                });
+                match game {
+                    Ok(game) => println!("Mine density: {:.1}%", game.board.density() * 100.0),
+                    Err(error) => {
+                        println!("{}", error.to_string().red());
+                        continue;
+                    }
+                }
+            }
+            break;
+        }
+    }
+    let settings = GameSettings::builder()
+        .width(params[0])
+        .height(params[1])
+        .mines(params[2])
+        .max_tiles(options.max_board_size)
+        .build()?;
+    Ok(settings)
+}
+
+/// Reads the next command while the clock below the board keeps running. The
+/// board is drawn again each time the terminal is resized in the meantime,
+/// where that can be watched for (see [`ResizeWatcher`]).
+fn wait_for_input(
+    game: &Minesweeper,
+    screen: &mut Screen,
+    view: &View,
+    options: &Options,
+) -> Result<Action, InputError> {
+    let dimensions = (game.board.width, game.board.height);
+    let default_action = options.default_action;
+    let letters = options.letters;
+    // Started before anything reads stdin, as it may ask the terminal where
+    // the cursor is
+    let mut ticker = start_ticker(game, screen, options);
+    let (sender, received) = mpsc::channel();
+    let resized = sender.clone();
+    // Frames printed one after another are left as they are
+    let watcher = match options.quiet {
+        true => None,
+        false => ResizeWatcher::start(move || {
+            let _ = resized.send(None);
+        }),
+    };
+    let Some(_watcher) = watcher else {
+        return take_input(dimensions, default_action, letters);
+    };
+    thread::spawn(move || {
+        let _ = sender.send(Some(take_input(dimensions, default_action, letters)));
+    });
+    loop {
+        match received.recv() {
+            Ok(Some(input)) => return input,
+            Ok(None) if screen.resized() => {
+                // Stop the clock first so it isn't drawn over the new frame
+                drop(ticker.take());
+                render(game, screen, view, options);
+                ticker =
+                    clock_start(game, options).and_then(|start| screen.ticker_after_draw(start));
+            }
+            Ok(None) => (),
+            Err(_) => return Ok(Action::ForceQuit),
+        }
+    }
+}
+
+pub fn take_input(
+    dimensions: (usize, usize),
+    default_action: DefaultAction,
+    letters: bool,
+) -> Result<Action, InputError> {
+    let mut line = String::new();
+    if std::io::stdin().read_line(&mut line)? == 0 {
+        // End of input, e.g. Ctrl-D or a closed pipe
+        return Ok(Action::ForceQuit);
+    }
+    parse_input(&line, dimensions, default_action, letters)
+}
+
+/// Parses a line of input. Several space separated commands, like
+/// `f3,4 f3,5 9,2`, are returned as one `Action::Batch`; if any of them is
+/// invalid the whole line is rejected. Tiles can be given as letters and a
+/// number, like `c7`, when the columns are labelled with `letters`.
+pub fn parse_input(
+    line: &str,
+    dimensions: (usize, usize),
+    default_action: DefaultAction,
+    letters: bool,
+) -> Result<Action, InputError> {
+    let single = parse_command(line, dimensions, default_action, letters);
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if single.is_ok()
+        || tokens.len() < 2
+        || parse_command(tokens[0], dimensions, default_action, letters).is_err()
+    {
+        return single;
+    }
+    let actions = tokens
+        .iter()
+        .map(
+            |token| match parse_command(token, dimensions, default_action, letters)? {
+                Action::RepeatLast(_) => Err(InputError::Malformed(format!(
+                    "'{}' can't be combined with other commands",
+                    token
+                ))),
+                action => Ok(action),
+            },
+        )
+        .collect::<Result<_, _>>()?;
+    Ok(Action::Batch(actions))
+}
+
+fn parse_command(
+    line: &str,
+    dimensions: (usize, usize),
+    default_action: DefaultAction,
+    letters: bool,
+) -> Result<Action, InputError> {
+    // Without letters on the columns, `q7` or `p3` is more likely a typo than
+    // a tile
+    let parse_letter_coordinate = |s: &str| match letters {
+        true => parse_letter_coordinate(s),
+        false => None,
+    };
+    let input = line.trim();
+    let mut line = input;
+
+    let Some(first_char) = line.chars().next() else {
+        return Err(InputError::Empty);
+    };
+    // Command letters are case insensitive
+    let command = first_char.to_ascii_lowercase();
+
+    if line.eq_ignore_ascii_case("q") {
+        return Ok(Action::Quit);
+    }
+
+    if line.eq_ignore_ascii_case("q!") {
+        return Ok(Action::ForceQuit);
+    }
+
+    if line.eq_ignore_ascii_case("p") {
+        return Ok(Action::Pause);
+    }
+
+    if line.eq_ignore_ascii_case(":h") {
+        return Ok(Action::History);
+    }
+
+    if line == "?" || line.eq_ignore_ascii_case("help") {
+        return Ok(Action::Help);
+    }
+
+    if line.eq_ignore_ascii_case(":a") {
+        return Ok(Action::Auto);
+    }
+
+    if line.eq_ignore_ascii_case("solvable?") {
+        return Ok(Action::Solvable);
+    }
+
+    match line {
+        "<" => return Ok(Action::Page(-1, 0)),
+        ">" => return Ok(Action::Page(1, 0)),
+        "^" => return Ok(Action::Page(0, -1)),
+        "v" | "V" => return Ok(Action::Page(0, 1)),
+        _ => (),
+    }
+
+    // Commands written out in full, followed by a tile. `read x,y` (or `peek
+    // x,y`) describes a tile without touching it, `preview x,y` shows where
+    // the first sweep would be safe.
+    type TileAction = fn(usize, usize) -> Action;
+    let word_commands: [(&str, TileAction); 3] = [
+        ("read", Action::Read),
+        ("peek", Action::Read),
+        ("preview", Action::Preview),
+    ];
+    for (word, action) in word_commands {
+        let Some(rest) = line
+            .get(..word.len())
+            .filter(|start| start.eq_ignore_ascii_case(word))
+            .map(|_| line[word.len()..].trim())
+        else {
+            continue;
+        };
+        let (x, y) = match parse_letter_coordinate(rest) {
+            Some((x, y)) => (x + 1, y + 1),
+            None => parse_coordinates(rest).map_err(|error| {
+                InputError::Malformed(format!("Invalid Location: '{}': {}", input, error))
+            })?,
+        };
+        check_bounds(input, x, y, dimensions)?;
+        return Ok(action(x - 1, y - 1));
+    }
+
+    // `highlight N` picks out the tiles showing N
+    if let Some(rest) = line
+        .get(..9)
+        .filter(|start| start.eq_ignore_ascii_case("highlight"))
+        .map(|_| line[9..].trim())
+    {
+        return match rest.parse().ok().filter(|n| (1..=8).contains(n)) {
+            Some(n) => Ok(Action::Highlight(TileState::from_count(n))),
+            None => Err(InputError::Malformed(format!(
+                "Invalid number: '{}': expected 1-8",
+                input
+            ))),
+        };
+    }
+
+    // `row N` describes a whole row
+    if let Some(rest) = line
+        .get(..3)
+        .filter(|start| start.eq_ignore_ascii_case("row"))
+        .map(|_| line[3..].trim())
+    {
+        if let Ok(y) = rest.parse::<usize>() {
+            if !(1..=dimensions.1).contains(&y) {
+                return Err(InputError::Malformed(format!(
+                    "Invalid row: '{}': row {} is outside 1-{}",
+                    input, y, dimensions.1
+                )));
+            }
+            return Ok(Action::ReadRow(y - 1));
+        }
+    }
+
+    if first_char == '.' {
+        return match &line[1..] {
+            "" => Ok(Action::RepeatLast(0)),
+            "+" => Ok(Action::RepeatLast(1)),
+            "-" => Ok(Action::RepeatLast(-1)),
+            _ => Err(InputError::Malformed(format!(
+                "Unknown repeat command: '{}', use ., .+ or .-",
+                input
+            ))),
+        };
+    }
+
+    // Letter-number coordinates such as `c7` or `7c`, with `--letters`. A
+    // leading command letter always means the command, so `fc7` flags C7 and
+    // `f7` is a flag command missing its row. Columns F and S have to be
+    // written as `7f` and `7s`.
+    let rest = &line[first_char.len_utf8()..];
+    let letter_action = match (command, parse_letter_coordinate(rest)) {
+        ('s', Some((x, y))) => Some(Action::Sweep(x, y)),
+        ('f', Some((x, y))) => Some(Action::Flag(x, y)),
+        ('?', Some((x, y))) => Some(Action::Question(x, y)),
+        ('s' | 'f', None) => None,
+        _ => parse_letter_coordinate(line).map(|(x, y)| default_action.action(x, y)),
+    };
+    if let Some(action) = letter_action {
+        return Ok(action);
+    }
+
+    if command == 'q' {
+        // Anything after a `q` is most likely a typo, so don't quit on it
+        return Err(InputError::Malformed(format!(
+            "'{}' isn't a command, type q on its own to quit",
+            input
+        )));
+    }
+
+    let has_command = first_char.is_alphabetic() || first_char == '?';
+    if has_command {
+        if !matches!(command, 's' | 'f' | '?') {
+            return Err(InputError::Malformed(format!(
+                "Unknown command '{}', type help for the list of commands",
+                first_char
+            )));
+        }
+        line = &line[first_char.len_utf8()..];
+    }
+
+    if command == 'f' {
+        let tiles: Option<Vec<(usize, usize)>> = line
+            .split_whitespace()
+            .map(|pair| parse_coordinates(pair).ok())
+            .collect();
+        if let Some(tiles) = tiles.filter(|tiles| tiles.len() > 1) {
+            let tiles = tiles.into_iter().map(|(x, y)| zero_based(input, x, y));
+            return Ok(Action::FlagMany(tiles.collect::<Result<_, _>>()?));
+        }
+    }
+
+    let (x, y) = parse_coordinates(line).map_err(|error| {
+        InputError::Malformed(format!("Invalid Location: '{}': {}", input, error))
+    })?;
+    // Moves off the board are left to the game to refuse
+    let (x, y) = zero_based(input, x, y)?;
+
+    let action = match command {
+        's' => Action::Sweep(x, y),
+        'f' => Action::Flag(x, y),
+        '?' => Action::Question(x, y),
+        _ => default_action.action(x, y),
+    };
+
+    Ok(action)
+}
+
+/// Parses a pair of one-based coordinates separated by `,`, `;`, whitespace
+/// or `x`, such as `3,7`, `3;7`, `3 7` or `3x7`. Stray punctuation around the
+/// pair is ignored, but anything other than exactly two numbers is an error.
+fn parse_coordinates(s: &str) -> Result<(usize, usize), InputError> {
+    let s = s.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    let parts: Vec<&str> = if s.contains([',', ';']) {
+        s.split([',', ';']).map(str::trim).collect()
+    } else if s.contains(char::is_whitespace) {
+        s.split_whitespace().collect()
+    } else {
+        s.split(['x', 'X']).collect()
+    };
+    let parse = |part: &str, name: &str| {
+        if part.is_empty() {
+            return Err(InputError::Malformed(format!("missing {}", name)));
+        }
+        part.parse::<usize>()
+            .map_err(|_| InputError::Malformed(format!("`{}` is not a number", part)))
+    };
+    let message = match parts[..] {
+        [x, y] => return Ok((parse(x, "column")?, parse(y, "row")?)),
+        [""] => "missing column and row".to_string(),
+        [_] => "missing row".to_string(),
+        _ => format!("expected a column and a row, found {} values", parts.len()),
+    };
+    Err(InputError::Malformed(message))
+}
+
+/// Turns typed, one-based coordinates into the game's zero-based ones.
+fn zero_based(input: &str, x: usize, y: usize) -> Result<(usize, usize), InputError> {
+    match (x.checked_sub(1), y.checked_sub(1)) {
+        (Some(x), Some(y)) => Ok((x, y)),
+        _ => Err(InputError::Malformed(format!(
+            "Invalid Location: '{}': rows and columns start at 1",
+            input
+        ))),
+    }
+}
+
+/// Checks one-based coordinates against the board.
+fn check_bounds(
+    input: &str,
+    x: usize,
+    y: usize,
+    dimensions: (usize, usize),
+) -> Result<(), InputError> {
+    let (width, height) = dimensions;
+    if (1..=width).contains(&x) && (1..=height).contains(&y) {
+        return Ok(());
+    }
+    Err(InputError::OutOfRange {
+        input: input.to_string(),
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+/// Parses a coordinate written as column letters and a row number, in either
+/// order (`c7`, `C7`, `7c`). Returns zero-based `(x, y)`.
+fn parse_letter_coordinate(s: &str) -> Option<(usize, usize)> {
+    let digits_first = s.starts_with(|c: char| c.is_ascii_digit());
+    let split = s.find(|c: char| c.is_ascii_digit() != digits_first)?;
+    let (first, second) = s.split_at(split);
+    let (letters, digits) = if digits_first {
+        (second, first)
+    } else {
+        (first, second)
+    };
+    let x = letters_to_column(letters)?;
+    let y = digits.parse::<usize>().ok()?.checked_sub(1)?;
+    Some((x, y))
+}
+
+/// Converts a zero-based column index into spreadsheet style letters:
+/// A–Z, then AA, AB, and so on.
+pub fn column_to_letters(mut column: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (column % 26) as u8);
+        if column < 26 {
+            break;
+        }
+        column = column / 26 - 1;
+    }
+    letters.iter().rev().map(|&c| c as char).collect()
+}
+
+/// Inverse of [`column_to_letters`]. Case insensitive.
+pub fn letters_to_column(letters: &str) -> Option<usize> {
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut column: usize = 0;
+    for c in letters.chars() {
+        let value = (c.to_ascii_uppercase() as u8 - b'A') as usize + 1;
+        column = column.checked_mul(26)?.checked_add(value)?;
+    }
+    Some(column - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws the board as plain text, without the colors.
+    fn draw(game: &Minesweeper, options: &Options) -> String {
+        colored::control::set_override(true);
+        let frame = render_to_string(game, options, &View::default());
+        let mut plain = String::new();
+        let mut chars = frame.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.find(|&c| c == 'm');
+            } else {
+                plain.push(c);
+            }
+        }
+        plain
+    }
+
+    /// Parses a line typed on an expert board, without `--letters`.
+    fn parse(line: &str) -> Result<Action, InputError> {
+        parse_input(line, (30, 16), DefaultAction::Sweep, false)
+    }
+
+    #[test]
+    fn letter_coordinates_need_letters() {
+        for line in ["q7", "p3", "v3", "c5"] {
+            assert!(parse(line).is_err(), "{}", line);
+        }
+        let letters = |line| parse_input(line, (30, 16), DefaultAction::Sweep, true);
+        assert!(matches!(letters("q7"), Ok(Action::Sweep(16, 6))));
+        assert!(matches!(letters("5c"), Ok(Action::Sweep(2, 4))));
+    }
+
+    #[test]
+    fn question_marks_differ_from_hidden_tiles() {
+        let mut game = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+        game.question(1, 0).unwrap();
+        let glyph = |x| {
+            game.board
+                .get(x, 0)
+                .unwrap()
+                .render(false, false, Theme::Classic)
+        };
+        assert_eq!(&*glyph(0), "#");
+        assert_eq!(&*glyph(1), "?");
+    }
+
+    #[test]
+    fn default_options_match_no_arguments() {
+        let options = Options::default();
+        assert_eq!(options.cell_width, 2);
+        assert_eq!(options.lives, 1);
+        assert_eq!(options.max_board_size, minesweeper::DEFAULT_MAX_TILES);
+    }
+
+    #[test]
+    fn suggested_mines_are_the_same_either_way_round() {
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            for (width, height) in [(30, 16), (9, 9), (100, 7), (2, 1)] {
+                assert_eq!(
+                    suggest_mines(width, height, difficulty),
+                    suggest_mines(height, width, difficulty)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn classic_boards_get_their_classic_mines() {
+        assert_eq!(suggest_mines(9, 9, Difficulty::Easy), 10);
+        assert_eq!(suggest_mines(16, 16, Difficulty::Medium), 40);
+        assert_eq!(suggest_mines(30, 16, Difficulty::Hard), 99);
+        assert_eq!(suggest_mines(16, 30, Difficulty::Hard), 99);
+    }
+
+    #[test]
+    fn harder_boards_get_more_mines() {
+        let mines = |difficulty| suggest_mines(40, 40, difficulty);
+        assert!(mines(Difficulty::Easy) < mines(Difficulty::Medium));
+        assert!(mines(Difficulty::Medium) < mines(Difficulty::Hard));
+    }
+
+    #[test]
+    fn only_yes_confirms() {
+        assert_eq!(parse_answer("y\n"), Some(true));
+        assert_eq!(parse_answer("n\n"), Some(false));
+        assert_eq!(parse_answer("\n"), None);
+        assert_eq!(parse_answer(""), None);
+    }
+
+    #[test]
+    fn wide_tiles_take_two_columns() {
+        let game = Minesweeper::from_layout(10, 3, &[(0, 0)]).unwrap();
+        let width = |cell_width, compact| {
+            let options = Options {
+                cell_width,
+                compact,
+                force_width: Some(200),
+                ..Options::default()
+            };
+            let frame = draw(&game, &options);
+            let row = frame.lines().find(|line| line.starts_with("1┃")).unwrap();
+            row.chars().count()
+        };
+        // The row number and the borders either side, then the tiles
+        assert_eq!(width(1, false), 3 + 10);
+        assert_eq!(width(2, false), 3 + 20);
+        assert_eq!(width(2, true), 3 + 20);
+    }
+
+    #[test]
+    fn wide_ruler_numbers_end_over_their_column() {
+        let game = Minesweeper::from_layout(10, 3, &[(0, 0)]).unwrap();
+        let options = Options {
+            compact: true,
+            force_width: Some(200),
+            ..Options::default()
+        };
+        let frame = draw(&game, &options);
+        let lines: Vec<&str> = frame.lines().collect();
+        assert_eq!(lines[0], " ┃                 10 ┃");
+        assert_eq!(lines[1], " ┃        |         | ┃");
+    }
+
+    #[test]
+    fn board_position_finds_the_tile_drawn_there() {
+        let mut game = Minesweeper::from_layout(12, 11, &[(0, 0)]).unwrap();
+        game.flag(10, 9).unwrap();
+        for cell_width in 1..=3 {
+            let options = Options {
+                cell_width,
+                force_width: Some(200),
+                ..Options::default()
+            };
+            let flag = game
+                .board
+                .get(10, 9)
+                .unwrap()
+                .render(false, false, options.theme);
+            let frame = draw(&game, &options);
+            let (row, line) = frame
+                .lines()
+                .enumerate()
+                .find(|(_, line)| line.contains(&*flag))
+                .unwrap();
+            let column = line.chars().position(|c| c.to_string() == *flag).unwrap();
+            let view = View::default();
+            let at = |column, row| board_position(&game, &options, &view, column, row);
+            assert_eq!(at(column, row), Some((10, 9)));
+            // The row numbers, the border and the column labels aren't tiles
+            assert_eq!(at(0, row), None);
+            assert_eq!(at(2, row), None);
+            assert_eq!(at(column, 0), None);
+        }
+    }
+
+    #[test]
+    fn replays_quit_without_asking_until_a_tile_is_swept() {
+        let mut game = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+        game.sweep(2, 0).unwrap();
+        game.reset_keep_mines();
+        assert!(game.in_progress());
+        // Asking would wait for an answer on stdin
+        assert!(matches!(
+            apply(&mut game, Action::Quit, &Options::default()),
+            Ok(false)
+        ));
+        assert_eq!(clock_start(&game, &Options::default()), None);
+    }
+
+    #[test]
+    fn moves_off_the_board_are_left_to_the_game() {
+        let mut game = Minesweeper::from_layout(30, 16, &[(0, 0)]).unwrap();
+        let action = parse("31,2").unwrap();
+        assert!(matches!(action, Action::Sweep(30, 1)));
+        let error = apply(&mut game, action, &Options::default()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid Location: '31,2': column 31 is outside 1-30"
+        );
+        let action = parse("f 3,17").unwrap();
+        let error = apply(&mut game, action, &Options::default()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid Location: '3,17': row 17 is outside 1-16"
+        );
+        let options = Options {
+            letters: true,
+            ..Options::default()
+        };
+        let action = parse_input("AE2", (30, 16), DefaultAction::Sweep, true).unwrap();
+        let error = apply(&mut game, action, &options).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid Location: 'AE2': column 31 is outside 1-30"
+        );
+        assert!(parse("0,2").is_err());
+    }
+
+    #[test]
+    fn flagging_many_tiles_checks_them_all_first() {
+        let mut game = Minesweeper::from_layout(30, 16, &[(0, 0)]).unwrap();
+        let action = parse("f 1,1 31,1").unwrap();
+        assert!(apply(&mut game, action, &Options::default()).is_err());
+        assert_eq!(game.board.flags, 0);
+    }
+
+    #[test]
+    fn json_summary_describes_a_finished_game() {
+        let mut game = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        let summary = serde_json::to_value(GameSummary::new(&game)).unwrap();
+        assert_eq!(summary["result"], "win");
+        assert_eq!(summary["width"], 3);
+        assert_eq!(summary["height"], 1);
+        assert_eq!(summary["mines"], 1);
+        assert_eq!(summary["flags"], 0);
+        assert_eq!(summary["mines_left"], 1);
+        assert_eq!(summary["revealed"], 2);
+        assert_eq!(summary["flagged"], serde_json::json!([]));
+        assert_eq!(summary["board"], game.board.to_ascii_grid(true));
+        assert!(summary["fingerprint"].is_string());
+    }
+
+    #[test]
+    fn empty_lines_ask_for_a_command() {
+        for line in ["", "\n", "   \t\n"] {
+            assert!(matches!(parse(line), Err(InputError::Empty)), "{:?}", line);
+        }
+        assert_eq!(
+            parse("").err().unwrap().to_string(),
+            "please enter a command"
+        );
+    }
+
+    #[test]
+    fn errors_name_the_coordinate_and_its_range() {
+        let message = |line| parse(line).err().unwrap().to_string();
+        assert_eq!(
+            message("read 5,99"),
+            "Invalid Location: 'read 5,99': row 99 is outside 1-16"
+        );
+        assert_eq!(
+            message("preview 31,2"),
+            "Invalid Location: 'preview 31,2': column 31 is outside 1-30"
+        );
+        assert_eq!(
+            message("0,2"),
+            "Invalid Location: '0,2': rows and columns start at 1"
+        );
+    }
+
+    #[test]
+    fn coordinates_take_several_separators() {
+        for (line, expected) in [
+            ("3,7", Some((3, 7))),
+            ("3 7", Some((3, 7))),
+            ("3;7", Some((3, 7))),
+            ("3x7", Some((3, 7))),
+            ("(3, 7)", Some((3, 7))),
+            ("3,,7", None),
+            ("3", None),
+            ("3,7,1", None),
+            ("", None),
+        ] {
+            assert_eq!(parse_coordinates(line).ok(), expected, "{:?}", line);
+        }
+        assert!(matches!(parse("f 3 7"), Ok(Action::Flag(2, 6))));
+        assert!(matches!(parse("s3;7"), Ok(Action::Sweep(2, 6))));
+        assert!(matches!(parse("3x7"), Ok(Action::Sweep(2, 6))));
+        assert!(parse("3,,7").is_err());
+    }
+
+    #[test]
+    fn one_flag_command_flags_several_tiles() {
+        let Ok(Action::FlagMany(tiles)) = parse("f1,1 2,2") else {
+            panic!("expected several flags");
+        };
+        assert_eq!(tiles, [(0, 0), (1, 1)]);
+        // A single pair separated by a space is still one tile
+        assert!(matches!(parse("f 1 2"), Ok(Action::Flag(0, 1))));
+    }
+
+    #[test]
+    fn input_errors_say_what_went_wrong() {
+        assert!(matches!(parse(" "), Err(InputError::Empty)));
+        assert!(matches!(
+            parse("read 31,16"),
+            Err(InputError::OutOfRange {
+                x: 31,
+                y: 16,
+                width: 30,
+                height: 16,
+                ..
+            })
+        ));
+        for line in ["3", "a,b", "z3,4", ".x", "highlight 9"] {
+            assert!(
+                matches!(parse(line), Err(InputError::Malformed(_))),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_coordinates_say_what_is_wrong() {
+        let message = |s| parse_coordinates(s).err().unwrap().to_string();
+        assert_eq!(message("3"), "missing row");
+        assert_eq!(message(""), "missing column and row");
+        assert_eq!(message("x,7"), "`x` is not a number");
+        assert_eq!(message("3,"), "missing row");
+        assert_eq!(
+            message("3,7,1"),
+            "expected a column and a row, found 3 values"
+        );
+        assert_eq!(
+            parse("fx,7").err().unwrap().to_string(),
+            "Invalid Location: 'fx,7': `x` is not a number"
+        );
+    }
+
+    #[test]
+    fn quiet_frames_are_just_the_board() {
+        let game = Minesweeper::from_layout(9, 9, &[(0, 0)]).unwrap();
+        let frame = |quiet| {
+            let options = Options {
+                quiet,
+                force_width: Some(200),
+                ..Options::default()
+            };
+            let frame = render_to_string(&game, &options, &View::default());
+            Screen::new(&options).draw(&frame)
+        };
+        let quiet = frame(true);
+        assert!(!quiet.contains(CLEAR));
+        assert!(!quiet.contains("Commands"));
+        let loud = frame(false);
+        assert!(loud.contains(CLEAR));
+        assert!(loud.contains("Commands"));
+    }
+
+    #[test]
+    fn command_letters_ignore_case() {
+        assert!(matches!(parse("F3,4"), Ok(Action::Flag(2, 3))));
+        assert!(matches!(parse("S3,4"), Ok(Action::Sweep(2, 3))));
+        assert!(matches!(parse("?3,4"), Ok(Action::Question(2, 3))));
+        assert!(matches!(parse("Q"), Ok(Action::Quit)));
+        assert!(matches!(parse("?"), Ok(Action::Help)));
+        assert!(matches!(parse("HELP"), Ok(Action::Help)));
+        for line in ["z3,4", "Z3,4", "x3,4", "m3,4"] {
+            assert_eq!(
+                parse(line).err().unwrap().to_string(),
+                format!(
+                    "Unknown command '{}', type help for the list of commands",
+                    &line[..1]
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn column_letters_carry_over_after_z() {
+        for (column, letters) in [
+            (0, "A"),
+            (25, "Z"),
+            (26, "AA"),
+            (27, "AB"),
+            (51, "AZ"),
+            (52, "BA"),
+            (701, "ZZ"),
+            (702, "AAA"),
+        ] {
+            assert_eq!(column_to_letters(column), letters);
+            assert_eq!(letters_to_column(letters), Some(column));
+        }
+        assert_eq!(letters_to_column("aa"), Some(26));
+        assert_eq!(letters_to_column(""), None);
+        assert_eq!(letters_to_column("A1"), None);
+        let letters = |line| parse_input(line, (30, 16), DefaultAction::Sweep, true);
+        assert!(matches!(letters("Z3"), Ok(Action::Sweep(25, 2))));
+        assert!(matches!(letters("fAA3"), Ok(Action::Flag(26, 2))));
+    }
+
+    #[test]
+    fn bare_coordinates_take_the_default_action() {
+        let with = |line, default| parse_input(line, (30, 16), default, false);
+        assert!(matches!(
+            with("3,4", DefaultAction::Sweep),
+            Ok(Action::Sweep(2, 3))
+        ));
+        assert!(matches!(
+            with("3,4", DefaultAction::Flag),
+            Ok(Action::Flag(2, 3))
+        ));
+        // Prefixes still say what to do
+        assert!(matches!(
+            with("s3,4", DefaultAction::Flag),
+            Ok(Action::Sweep(2, 3))
+        ));
+        assert!(matches!(
+            with("?3,4", DefaultAction::Flag),
+            Ok(Action::Question(2, 3))
+        ));
+        assert!(matches!(
+            with("f3,4", DefaultAction::Sweep),
+            Ok(Action::Flag(2, 3))
+        ));
+    }
+
+    #[test]
+    fn quit_takes_nothing_after_it() {
+        assert!(matches!(parse("q"), Ok(Action::Quit)));
+        assert!(matches!(parse("  q \n"), Ok(Action::Quit)));
+        assert!(matches!(parse("q!"), Ok(Action::ForceQuit)));
+        for line in ["q1,2", "qfoo"] {
+            assert_eq!(
+                parse(line).err().unwrap().to_string(),
+                format!("'{}' isn't a command, type q on its own to quit", line)
+            );
+        }
+    }
+
+    #[test]
+    fn tiles_are_described_in_words() {
+        let mut game = Minesweeper::from_layout(4, 1, &[(3, 0)]).unwrap();
+        game.sweep(2, 0).unwrap();
+        game.question(1, 0).unwrap();
+        // The only mine, so this wins the game
+        game.flag(3, 0).unwrap();
+        let describe = |x| game.board.get(x, 0).unwrap().describe();
+        assert_eq!(describe(0), "hidden");
+        assert_eq!(describe(1), "hidden, marked with a question mark");
+        assert_eq!(describe(2), "1");
+        assert_eq!(describe(3), "flagged");
+        assert!(matches!(parse("read 3,5"), Ok(Action::Read(2, 4))));
+        assert!(matches!(parse("peek 3,5"), Ok(Action::Read(2, 4))));
+    }
+
+    #[test]
+    fn wide_boards_split_into_pages() {
+        assert_eq!(page_ranges(100, 40), [0..40, 40..80, 80..100]);
+        assert_eq!(page_ranges(100, 100).len(), 1);
+        assert_eq!(page_ranges(100, 0).len(), 100);
+
+        let game = Minesweeper::from_layout(100, 9, &[(0, 0)]).unwrap();
+        let options = Options {
+            cell_width: 1,
+            force_width: Some(44),
+            ..Options::default()
+        };
+        // Two columns for the row numbers and the borders either side leave
+        // room for 40 tiles
+        assert_eq!(visible_columns(&game, &options, 0), 0..40);
+        assert_eq!(visible_columns(&game, &options, 2), 80..100);
+        assert_eq!(visible_columns(&game, &options, 9), 80..100);
+    }
+
+    #[test]
+    fn menu_choices_are_read_by_letter_or_word() {
+        for (line, choice) in [
+            ("r\n", MenuChoice::Replay),
+            ("Replay", MenuChoice::Replay),
+            ("n", MenuChoice::NewBoard),
+            (" NEW ", MenuChoice::NewBoard),
+            ("s", MenuChoice::NewSettings),
+            ("settings", MenuChoice::NewSettings),
+            ("Q", MenuChoice::Quit),
+            ("quit\n", MenuChoice::Quit),
+        ] {
+            assert_eq!(parse_menu_choice(line), Some(choice), "{:?}", line);
+        }
+        for line in ["", "x", "rn", "replay board"] {
+            assert_eq!(parse_menu_choice(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn the_border_shows_how_the_game_ended() {
+        let options = Options {
+            force_width: Some(200),
+            ..Options::default()
+        };
+        // The line under the column labels, with its colors
+        let bar = |game: &Minesweeper| {
+            colored::control::set_override(true);
+            let frame = render_to_string(game, &options, &View::default());
+            let line = frame.lines().find(|line| line.contains('╋')).unwrap();
+            line.to_string()
+        };
+        let playing = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+        let mut won = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+        won.sweep(0, 0).unwrap();
+        let mut lost = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+        lost.sweep(2, 0).unwrap();
+        assert!(won.won() && lost.lost());
+
+        let plain = draw(&playing, &options);
+        let plain = plain.lines().find(|line| line.contains('╋')).unwrap();
+        assert_eq!(bar(&playing), plain.white().to_string());
+        assert_eq!(bar(&won), plain.green().to_string());
+        assert_eq!(bar(&lost), plain.red().to_string());
+    }
+
+    #[test]
+    fn frames_show_the_board_as_it_is_played() {
+        let options = Options {
+            force_width: Some(200),
+            ..Options::default()
+        };
+        // The frame without the status line, which shows the time
+        let board = |game: &Minesweeper| {
+            let frame = draw(game, &options);
+            let lines: Vec<&str> = frame.lines().collect();
+            lines[..lines.len() - 1].join("\n")
+        };
+        let mut game = Minesweeper::from_layout(5, 4, &[(4, 0), (4, 3)]).unwrap();
+        assert_eq!(
+            board(&game),
+            " ┃1 2 3 4 5 ┃\n\
+             ━╋━━━━━━━━━━┫\n\
+             1┃# # # # # ┃\n\
+             2┃# # # # # ┃\n\
+             3┃# # # # # ┃\n\
+             4┃# # # # # ┃\n\
+             ━┻━━━━━━━━━━┛"
+        );
+        game.sweep(0, 0).unwrap();
+        game.flag(4, 1).unwrap();
+        assert_eq!(
+            board(&game),
+            " ┃1 2 3 4 5 ┃\n\
+             ━╋━━━━━━━━━━┫\n\
+             1┃      1 # ┃\n\
+             2┃      1 F ┃\n\
+             3┃      1 # ┃\n\
+             4┃      1 # ┃\n\
+             ━┻━━━━━━━━━━┛"
+        );
+        // Losing shows the other mines, and the one swept
+        game.sweep(4, 3).unwrap();
+        assert_eq!(
+            board(&game),
+            " ┃1 2 3 4 5 ┃\n\
+             ━╋━━━━━━━━━━┫\n\
+             1┃      1 * ┃\n\
+             2┃      1 F ┃\n\
+             3┃      1 # ┃\n\
+             4┃      1 X ┃\n\
+             ━┻━━━━━━━━━━┛"
+        );
+    }
+
+    #[test]
+    fn the_last_frame_shows_every_mine() {
+        let options = Options {
+            force_width: Some(200),
+            ..Options::default()
+        };
+        let board = |game: &Minesweeper| {
+            let frame = draw(game, &options);
+            let lines: Vec<&str> = frame.lines().collect();
+            lines[2..4].join("\n")
+        };
+        // Won by sweeping, with a mine left unflagged
+        let mut game = Minesweeper::from_layout(4, 2, &[(3, 0), (3, 1)]).unwrap();
+        game.flag(3, 0).unwrap();
+        game.sweep(0, 0).unwrap();
+        assert!(game.won());
+        assert_eq!(board(&game), "1┃    2 F ┃\n2┃    2 F ┃");
+        // Lost, with a mine left unflagged
+        let mut game = Minesweeper::from_layout(4, 2, &[(3, 0), (3, 1)]).unwrap();
+        game.sweep(3, 1).unwrap();
+        assert!(game.lost());
+        assert_eq!(board(&game), "1┃# # # * ┃\n2┃# # # X ┃");
+    }
+
+    #[test]
+    fn peeking_leaves_the_board_alone() {
+        let options = Options::default();
+        let mut game = Minesweeper::new(&GameSettings::beginner()).unwrap();
+        apply(&mut game, parse("peek 5,5").unwrap(), &options).unwrap();
+        assert_eq!(game.state, GameState::Empty);
+
+        let mut game = Minesweeper::from_layout(3, 3, &[(0, 0)]).unwrap();
+        apply(&mut game, parse("peek 3,3").unwrap(), &options).unwrap();
+        assert!(!game.board.get(2, 2).unwrap().swept);
+        assert_eq!(game.board.swept_count(), 0);
+        assert_eq!(game.moves, 0);
+    }
+
+    #[test]
+    fn highlighting_picks_out_one_number() {
+        colored::control::set_override(true);
+        let options = Options {
+            force_width: Some(200),
+            ..Options::default()
+        };
+        let mut game = Minesweeper::from_layout(4, 3, &[(3, 0), (3, 2)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        assert_eq!(game.board.to_ascii_grid(false), "  1#\n  2#\n  1#");
+        let view = View {
+            highlight: Some(TileState::One),
+            ..View::default()
+        };
+        let frame = render_to_string(&game, &options, &view);
+        // The styles of the numbers on the board, as `(reversed, dimmed)`
+        let mut numbers = Vec::new();
+        for (i, _) in frame.match_indices('\x1b') {
+            let Some((styles, rest)) = frame[i + 2..].split_once('m') else {
+                continue;
+            };
+            let styles: Vec<&str> = styles.split(';').collect();
+            if let Some(number @ '1'..='8') = rest.chars().next() {
+                numbers.push((number, styles.contains(&"7"), styles.contains(&"2")));
+            }
+        }
+        numbers.retain(|&(_, reversed, dimmed)| reversed || dimmed);
+        assert_eq!(
+            numbers,
+            [('1', true, false), ('2', false, true), ('1', true, false)]
+        );
+    }
+
+    #[test]
+    fn every_command_in_the_help_works() {
+        let commands: Vec<&str> = HELP
+            .lines()
+            .filter_map(|line| line.strip_prefix("  "))
+            .map(|line| line[..21].trim())
+            .collect();
+        assert!(commands.len() > 15);
+        for command in commands {
+            // The first way of writing each command, with a tile or a number
+            let example = command.split(", ").next().unwrap();
+            let example = example.replace("x,y", "3,4").replace(" n", " 2");
+            let parsed = parse_input(&example, (30, 16), DefaultAction::Sweep, true);
+            assert!(parsed.is_ok(), "{} from {}", example, command);
+        }
+        for word in ["sweep", "flag", "quit", "pause", "help"] {
+            assert!(HELP.contains(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn probabilities_cover_only_hidden_tiles() {
+        let options = Options {
+            show_probabilities: true,
+            force_width: Some(200),
+            ..Options::default()
+        };
+        let mut game = Minesweeper::from_layout(4, 3, &[(3, 0), (3, 2)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        let frame = draw(&game, &options);
+        let rows: Vec<&str> = frame.lines().skip(2).take(3).collect();
+        // Only the mines fit all three numbers
+        assert_eq!(rows, ["1┃    1 ! ┃", "2┃    2 ⁰ ┃", "3┃    1 ! ┃"]);
+
+        // With nothing swept, every tile is as likely as any other
+        let game = Minesweeper::from_layout(4, 1, &[(0, 0), (3, 0)]).unwrap();
+        let frame = draw(&game, &options);
+        assert_eq!(frame.lines().nth(2), Some("1┃⁵ ⁵ ⁵ ⁵ ┃"));
+    }
+
+    #[test]
+    fn start_positions_are_read_in_each_form() {
+        assert_eq!(StartPosition::parse("center"), Ok(StartPosition::Center));
+        assert_eq!(StartPosition::parse("Centre"), Ok(StartPosition::Center));
+        assert_eq!(StartPosition::parse("corner"), Ok(StartPosition::Corner));
+        assert_eq!(StartPosition::parse("3,5"), Ok(StartPosition::Tile(2, 4)));
+        assert_eq!(StartPosition::parse("3 5"), Ok(StartPosition::Tile(2, 4)));
+        assert!(StartPosition::parse("0,5").is_err());
+        assert!(StartPosition::parse("middle").is_err());
+        assert!(StartPosition::parse("3").is_err());
+
+        assert_eq!(StartPosition::Center.tile(30, 16), (14, 7));
+        assert_eq!(StartPosition::Center.tile(9, 9), (4, 4));
+        assert_eq!(StartPosition::Corner.tile(30, 16), (0, 0));
+        assert_eq!(StartPosition::Tile(2, 4).tile(30, 16), (2, 4));
+    }
+
+    #[test]
+    fn high_contrast_tiles_have_their_own_characters() {
+        let options = Options {
+            theme: Theme::HighContrast,
+            force_width: Some(200),
+            ..Options::default()
+        };
+        let board = |game: &Minesweeper| {
+            let frame = draw(game, &options);
+            let lines: Vec<&str> = frame.lines().collect();
+            lines[..lines.len() - 1].join("\n")
+        };
+        let mut game = Minesweeper::from_layout(5, 4, &[(4, 0), (4, 3)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        game.flag(4, 1).unwrap();
+        game.question(4, 2).unwrap();
+        assert_eq!(
+            board(&game),
+            " ┃1 2 3 4 5 ┃\n\
+             ━╋━━━━━━━━━━┫\n\
+             1┃      1 ░ ┃\n\
+             2┃      1 ! ┃\n\
+             3┃      1 ? ┃\n\
+             4┃      1 ░ ┃\n\
+             ━┻━━━━━━━━━━┛"
+        );
+        game.sweep(4, 3).unwrap();
+        assert_eq!(
+            board(&game),
+            " ┃1 2 3 4 5 ┃\n\
+             ━╋━━━━━━━━━━┫\n\
+             1┃      1 @ ┃\n\
+             2┃      1 ! ┃\n\
+             3┃      1 ? ┃\n\
+             4┃      1 % ┃\n\
+             ━┻━━━━━━━━━━┛"
+        );
+    }
+
+    #[test]
+    fn high_contrast_tiles_have_their_own_colors() {
+        let mut tiles = Vec::new();
+        let mut game = Minesweeper::from_layout(3, 3, &[(0, 0)]).unwrap();
+        let hidden = *game.board.get(1, 1).unwrap();
+        game.question(2, 2).unwrap();
+        let unsure = *game.board.get(2, 2).unwrap();
+        game.flag(2, 1).unwrap();
+        let flagged = *game.board.get(2, 1).unwrap();
+        let mine = *game.board.get(0, 0).unwrap();
+        for (tile, game_over, detonated) in [
+            (hidden, false, false),
+            (unsure, false, false),
+            (flagged, false, false),
+            (mine, true, false),
+            (mine, true, true),
+        ] {
+            tiles.push(tile.render_high_contrast(game_over, detonated));
+        }
+        for count in 0..=8 {
+            let mut tile = hidden;
+            tile.swept = true;
+            tile.state = TileState::from_count(count);
+            tiles.push(tile.render_high_contrast(false, false));
+        }
+        for (i, tile) in tiles.iter().enumerate() {
+            assert!(tile.bgcolor().is_some(), "{}", tile);
+            assert!(tile.fgcolor().is_some(), "{}", tile);
+            for other in &tiles[i + 1..] {
+                let colors = |tile: &ColoredString| (tile.fgcolor(), tile.bgcolor());
+                assert!(colors(tile) != colors(other), "{} {}", tile, other);
+            }
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use crate::cli::{board_position, render_to_string, start_ticker, toggle_pause, Options, View};
+use crate::minesweeper::{GameEvent, Minesweeper};
+use crate::screen::Screen;
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::{cursor, execute, terminal};
+use std::io::{self, Write};
+
+/// Holds the terminal in raw mode, restoring it when dropped. See
+/// [`restore_terminal_on_panic`](crate::screen::restore_terminal_on_panic)
+/// for panics.
+struct RawMode;
+
+impl RawMode {
+    fn enable(mouse: bool) -> io::Result<RawMode> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), cursor::Hide)?;
+        if mouse {
+            // Not every terminal reports mouse events, carry on without them
+            let _ = execute!(io::stdout(), EnableMouseCapture);
+        }
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+fn restore_terminal() {
+    let _ = execute!(io::stdout(), DisableMouseCapture, cursor::Show);
+    let _ = terminal::disable_raw_mode();
+}
+
+const HELP: &str = "\
+Cursor mode keys
+
+  arrows, h j k l   move one tile
+  0, $              start / end of the row
+  gg, G             top / bottom row
+  5l, 3j, ...       a count before a motion repeats it
+  5G, 5gg           go to row 5
+  space             sweep
+  f                 flag
+  c                 chord
+  a                 make the obvious moves
+  p                 pause / resume
+  ?                 show this help
+  q, Esc            quit, asking first if a game is under way
+  Ctrl-C            quit without asking
+
+Press any key to return to the game.
+";
+
+/// Cursor movement, in tiles.
+enum Motion {
+    Left(usize),
+    Right(usize),
+    Up(usize),
+    Down(usize),
+    RowStart,
+    RowEnd,
+    /// Go to the given row, or the top row if `None`
+    Top(Option<usize>),
+    /// Go to the given row, or the bottom row if `None`
+    Bottom(Option<usize>),
+}
+
+impl Motion {
+    fn apply(&self, (x, y): (usize, usize), width: usize, height: usize) -> (usize, usize) {
+        let row = |row: Option<usize>, default| row.map_or(default, |r| r.clamp(1, height) - 1);
+        match *self {
+            Motion::Left(n) => (x.saturating_sub(n), y),
+            Motion::Right(n) => (x.saturating_add(n).min(width - 1), y),
+            Motion::Up(n) => (x, y.saturating_sub(n)),
+            Motion::Down(n) => (x, y.saturating_add(n).min(height - 1)),
+            Motion::RowStart => (0, y),
+            Motion::RowEnd => (width - 1, y),
+            Motion::Top(r) => (x, row(r, 0)),
+            Motion::Bottom(r) => (x, row(r, height - 1)),
+        }
+    }
+}
+
+enum Key {
+    Motion(Motion),
+    /// Part of a longer sequence, e.g. a count or the first `g` of `gg`
+    Pending,
+    /// Not a motion key
+    Other(char),
+}
+
+/// Vim style key sequences: an optional count followed by a motion.
+#[derive(Default)]
+struct KeySequence {
+    count: Option<usize>,
+    g: bool,
+}
+
+impl KeySequence {
+    fn push(&mut self, c: char) -> Key {
+        if self.g {
+            let count = self.count.take();
+            self.g = false;
+            return match c {
+                'g' => Key::Motion(Motion::Top(count)),
+                _ => Key::Other(c),
+            };
+        }
+        if let Some(digit) = c.to_digit(10) {
+            // A leading zero is the row start motion rather than a count
+            if digit != 0 || self.count.is_some() {
+                let count = self.count.unwrap_or(0);
+                self.count = Some(count.saturating_mul(10).saturating_add(digit as usize));
+                return Key::Pending;
+            }
+        }
+        if c == 'g' {
+            self.g = true;
+            return Key::Pending;
+        }
+        let count = self.count.take();
+        let n = count.unwrap_or(1);
+        Key::Motion(match c {
+            'h' => Motion::Left(n),
+            'l' => Motion::Right(n),
+            'k' => Motion::Up(n),
+            'j' => Motion::Down(n),
+            '0' => Motion::RowStart,
+            '$' => Motion::RowEnd,
+            'G' => Motion::Bottom(count),
+            _ => return Key::Other(c),
+        })
+    }
+
+    /// Count for a motion not typed as a character, such as an arrow key.
+    fn take_count(&mut self) -> usize {
+        self.g = false;
+        self.count.take().unwrap_or(1)
+    }
+}
+
+/// Game loop where a highlighted cursor is moved with the arrow keys (or vim
+/// style motions) and actions apply to the tile under it. With `options.mouse`, tiles can also
+/// be clicked: left sweeps, right flags and middle chords.
+pub fn play(game: &mut Minesweeper, options: &Options) -> io::Result<()> {
+    let _raw_mode = RawMode::enable(options.mouse)?;
+    let mut position = (0, 0);
+    let mut sequence = KeySequence::default();
+    let mut screen = Screen::new(options);
+    let mut help = false;
+    let mut confirm_quit = false;
+
+    loop {
+        // The whole board is redrawn after every key, so most events can be
+        // discarded
+        let rejection = game
+            .events
+            .drain()
+            .into_iter()
+            .rev()
+            .find_map(|event| match event {
+                GameEvent::Rejected { reason, .. } => Some(reason),
+                _ => None,
+            });
+        let mut view = View {
+            cursor: Some(position),
+            ..View::paged()
+        };
+        view.follow(game, options, position);
+        if help {
+            write_frame(&screen.draw(HELP))?;
+        } else {
+            let frame = render_to_string(game, options, &view);
+            write_frame(&screen.draw(&frame))?;
+        }
+        if confirm_quit {
+            write_frame("\nReally quit? (y/N)")?;
+        } else if let (Some(reason), false) = (rejection, help) {
+            write_frame(&format!("\n{}", reason))?;
+        }
+        if game.is_over() {
+            break;
+        }
+
+        let ticker = start_ticker(game, &screen, options);
+        let event = event::read()?;
+        drop(ticker);
+        if game.check_timeout() {
+            continue;
+        }
+        let (code, modifiers) = match event {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => (code, modifiers),
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(button),
+                column,
+                row,
+                ..
+            }) => {
+                if let Some((x, y)) =
+                    board_position(game, options, &view, column as usize, row as usize)
+                {
+                    position = (x, y);
+                    // Only tiles on the board are clicked, so the move
+                    // can't be off it
+                    let _ = match button {
+                        MouseButton::Left => game.sweep(x, y),
+                        MouseButton::Right => game.flag(x, y),
+                        MouseButton::Middle => game.chord(x, y),
+                    };
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if help {
+            help = false;
+            continue;
+        }
+        if confirm_quit {
+            if let KeyCode::Char('y' | 'Y') = code {
+                break;
+            }
+            confirm_quit = false;
+            continue;
+        }
+        let key = match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+            KeyCode::Char(c) => sequence.push(c),
+            KeyCode::Up => Key::Motion(Motion::Up(sequence.take_count())),
+            KeyCode::Down => Key::Motion(Motion::Down(sequence.take_count())),
+            KeyCode::Left => Key::Motion(Motion::Left(sequence.take_count())),
+            KeyCode::Right => Key::Motion(Motion::Right(sequence.take_count())),
+            KeyCode::Esc => Key::Other('q'),
+            _ => {
+                sequence = KeySequence::default();
+                continue;
+            }
+        };
+        let (x, y) = position;
+        match key {
+            Key::Motion(motion) => {
+                position = motion.apply(position, game.board.width, game.board.height)
+            }
+            Key::Pending => (),
+            // The cursor never leaves the board, so moves can't be off it
+            Key::Other(' ') => {
+                let _ = game.sweep(x, y);
+            }
+            Key::Other('f') => {
+                let _ = game.flag(x, y);
+            }
+            Key::Other('c') => {
+                let _ = game.chord(x, y);
+            }
+            Key::Other('a') => {
+                game.auto_step();
+            }
+            Key::Other('p') => toggle_pause(game),
+            Key::Other('?') => help = true,
+            Key::Other('q')
+                if game.in_progress() && game.board.swept_count() > 0 && options.confirm_quit =>
+            {
+                confirm_quit = true
+            }
+            Key::Other('q') => break,
+            Key::Other(_) => (),
+        }
+    }
+    Ok(())
+}
+
+fn write_frame(frame: &str) -> io::Result<()> {
+    // Raw mode doesn't translate newlines into carriage return + newline
+    let frame = frame.replace('\n', "\r\n");
+    let mut stdout = io::stdout();
+    stdout.write_all(frame.as_bytes())?;
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Moves the cursor from the middle of a 10x8 board by typing `keys`.
+    fn type_keys(keys: &str) -> (usize, usize) {
+        let mut sequence = KeySequence::default();
+        let mut cursor = (4, 4);
+        for c in keys.chars() {
+            match sequence.push(c) {
+                Key::Motion(motion) => cursor = motion.apply(cursor, 10, 8),
+                Key::Pending => (),
+                Key::Other(c) => panic!("{:?} isn't a motion", c),
+            }
+        }
+        cursor
+    }
+
+    #[test]
+    fn motions_move_the_cursor() {
+        assert_eq!(type_keys("h"), (3, 4));
+        assert_eq!(type_keys("l"), (5, 4));
+        assert_eq!(type_keys("k"), (4, 3));
+        assert_eq!(type_keys("j"), (4, 5));
+        assert_eq!(type_keys("0"), (0, 4));
+        assert_eq!(type_keys("$"), (9, 4));
+        assert_eq!(type_keys("gg"), (4, 0));
+        assert_eq!(type_keys("G"), (4, 7));
+    }
+
+    #[test]
+    fn counts_repeat_motions() {
+        assert_eq!(type_keys("3l"), (7, 4));
+        assert_eq!(type_keys("2k2h"), (2, 2));
+        // A count only lasts for one motion
+        assert_eq!(type_keys("3ll"), (8, 4));
+        // Counts stop at the edges
+        assert_eq!(type_keys("50h"), (0, 4));
+        assert_eq!(type_keys("10j"), (4, 7));
+    }
+
+    #[test]
+    fn counts_pick_a_row_for_gg_and_g() {
+        assert_eq!(type_keys("3gg"), (4, 2));
+        assert_eq!(type_keys("2G"), (4, 1));
+        assert_eq!(type_keys("99G"), (4, 7));
+    }
+
+    #[test]
+    fn unfinished_sequences_wait_for_more() {
+        let mut sequence = KeySequence::default();
+        assert!(matches!(sequence.push('1'), Key::Pending));
+        assert!(matches!(sequence.push('g'), Key::Pending));
+        assert!(matches!(sequence.push('x'), Key::Other('x')));
+        // The count went with the abandoned sequence
+        assert_eq!(sequence.take_count(), 1);
+        assert!(matches!(sequence.push('f'), Key::Other('f')));
+    }
+}
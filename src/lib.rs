@@ -0,0 +1,35 @@
+//! The minesweeper game engine behind the command line game, for use in
+//! other front ends.
+//!
+//! Everything in [`prelude`] is the stable API. The rest of [`minesweeper`]
+//! is public too, but may change along with the command line game.
+//!
+//! ```
+//! use minesweeper_cli::prelude::*;
+//!
+//! let settings = GameSettings {
+//!     width: 9,
+//!     height: 9,
+//!     mines: 10,
+//!     seed: Some(1),
+//! };
+//! let mut game = Minesweeper::new(&settings).unwrap();
+//! game.sweep(4, 4).unwrap();
+//! // The first sweep never hits a mine
+//! assert!(game.state != GameState::GameOver);
+//! while let Some(event) = game.events.next() {
+//!     if let GameEvent::RevealTile(x, y, tile) = event {
+//!         println!("{},{} is {}", x + 1, y + 1, tile.state);
+//!     }
+//! }
+//! ```
+
+pub mod minesweeper;
+
+/// The types needed to play a game.
+pub mod prelude {
+    pub use crate::minesweeper::{
+        GameBoard, GameEvent, GameSettings, GameSettingsBuilder, GameState, Minesweeper,
+        MinesweeperError, Tile, TileState,
+    };
+}
@@ -1,24 +1,30 @@
 mod cli;
-mod minesweeper;
-use crate::minesweeper::GameSettings;
-use clap::Parser;
+mod cursor;
+mod screen;
+mod stats;
+use clap::builder::RangedU64ValueParser;
+use clap::error::ErrorKind;
+use clap::{ArgAction, CommandFactory, Parser, ValueEnum};
+use minesweeper_cli::minesweeper::{self, GameSettings};
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Minesweeper
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    /// Size of game board
+    /// Size of game board, as WIDTHxHEIGHT or WIDTHxHEIGHTxMINES
     #[arg(
         long,
         short,
         value_parser = parse_dimensions
     )]
-    dimensions: Option<[usize; 2]>,
+    dimensions: Option<Dimensions>,
     /// Width of game board
     #[arg(
         long,
         requires("height"),
-        requires("mines"),
+        requires("mine_count"),
         conflicts_with = "dimensions"
     )]
     width: Option<usize>,
@@ -26,53 +32,309 @@ struct Args {
     #[arg(
         long,
         requires("width"),
-        requires("mines"),
+        requires("mine_count"),
         conflicts_with = "dimensions"
     )]
     height: Option<usize>,
     /// Number of mines
+    #[arg(short, long, group = "mine_count")]
+    mines: Option<usize>,
+    /// Pick the number of mines to suit the size of the board
+    #[arg(long, value_enum, group = "mine_count")]
+    difficulty_level: Option<cli::Difficulty>,
+    /// Seed for placing the mines, to play the same board again
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Sweep this tile as soon as the game begins: center, corner (the top
+    /// left) or a tile like 3,5
+    #[arg(long, value_name = "POSITION", value_parser = cli::StartPosition::parse)]
+    start: Option<cli::StartPosition>,
+    /// Board code shown during a seeded game, instead of the other settings
+    #[arg(
+        long,
+        value_parser = parse_code,
+        conflicts_with_all = ["dimensions", "width", "height", "mine_count", "seed"]
+    )]
+    code: Option<GameSettings>,
+    /// Print a JSON summary of the game once it ends
+    #[arg(long)]
+    json: bool,
+    /// Label columns with letters (A, B, ... AA) instead of numbers
+    #[arg(long)]
+    letters: bool,
+    /// Select tiles with the arrow keys instead of typing coordinates
+    #[arg(long)]
+    cursor: bool,
+    /// Click tiles with the mouse in cursor mode
+    #[arg(long, requires("cursor"))]
+    mouse: bool,
+    /// Don't clear the screen or show the list of commands
     #[arg(short, long)]
+    quiet: bool,
+    /// What typing coordinates without a command does
+    #[arg(long, value_enum, default_value_t)]
+    default_action: cli::DefaultAction,
+    /// Don't allow placing more flags than there are mines
+    #[arg(long)]
+    strict_flags: bool,
+    /// How the board is drawn after each move: by default the screen is
+    /// cleared and the whole board drawn again, `diff` rewrites only the
+    /// lines which changed
+    #[arg(long, value_enum, default_value_t)]
+    redraw: screen::Redraw,
+    /// Describe the board in words for screen readers, instead of drawing it
+    #[arg(long, visible_alias = "accessible", conflicts_with = "cursor")]
+    a11y: bool,
+    /// When to use colors: always, never, or when the terminal supports them
+    #[arg(long, value_enum, default_value_t)]
+    color: ColorChoice,
+    /// Colors and characters to draw the tiles with
+    #[arg(long, value_enum, default_value_t)]
+    theme: cli::Theme,
+    /// Which edges of the board to label with row and column numbers
+    #[arg(long, value_enum, default_value_t)]
+    labels: cli::Labels,
+    /// Show each hidden tile's chance of being a mine, worked out from the
+    /// numbers revealed so far
+    #[arg(long, conflicts_with = "a11y")]
+    show_probabilities: bool,
+    /// Terminal columns each tile takes up. The default of 2 makes tiles
+    /// about square on most terminals, 1 fits big boards and 3 spaces the
+    /// tiles out
+    #[arg(
+        long,
+        value_name = "COLUMNS",
+        default_value_t = 2,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..=3)
+    )]
+    cell_width: usize,
+    /// One column per tile with a ruler above the board, to fit huge boards
+    #[arg(long, conflicts_with = "cell_width")]
+    compact: bool,
+    /// Two columns per tile, the same as `--cell-width 2`. With `--compact`,
+    /// keeps the ruler but spaces the tiles out
+    #[arg(long, conflicts_with = "cell_width")]
+    wide: bool,
+    /// Lay the board out for a terminal this wide, e.g. when piping the output
+    #[arg(long, value_name = "COLUMNS")]
+    force_width: Option<usize>,
+    /// Mines which can be hit before the game is lost
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    lives: usize,
+    /// Let the first tile swept be a mine, for testing
+    #[arg(long, hide = true)]
+    allow_first_mine: bool,
+    /// Place the mines from the seed alone, so a seeded board is the same
+    /// wherever it's first swept. The first sweep is still never a mine, but
+    /// may not open up an area.
+    #[arg(long)]
+    fixed_layout: bool,
+    /// End the game after this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+    /// Play boards too big for the terminal without asking first
+    #[arg(long)]
+    force_size: bool,
+    /// Refuse boards with more tiles than this, so a typo doesn't run the
+    /// computer out of memory
+    #[arg(
+        long,
+        value_name = "TILES",
+        default_value_t = minesweeper::DEFAULT_MAX_TILES,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_board_size: usize,
+    /// Ask before quitting a game which is under way
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = ArgAction::Set)]
+    confirm_quit: bool,
+    /// Read commands from a file (or `-` for stdin) and print only the final board
+    #[arg(long, value_name = "PATH", conflicts_with = "cursor")]
+    script: Option<PathBuf>,
+    /// Print how many games have been played and won, then exit
+    #[arg(long)]
+    stats: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Dimensions {
+    width: usize,
+    height: usize,
     mines: Option<usize>,
 }
 
 fn main() {
     let args = Args::parse();
-    fn get_settings(args: &Args) -> Option<GameSettings> {
-        if args.mines.is_none() {
-            return None;
+    if args.stats {
+        match stats::path() {
+            Some(path) => print!("{}", stats::Stats::load(&path)),
+            None => eprintln!("No data directory to keep the stats in"),
         }
-        let [width, height] = if let (Some(width), Some(height)) = (args.height, args.width) {
-            [width, height]
-        } else if let Some(dimensions) = args.dimensions {
-            dimensions
+        return;
+    }
+    if let (Some(Dimensions { mines: Some(_), .. }), true) = (
+        args.dimensions,
+        args.mines.is_some() || args.difficulty_level.is_some(),
+    ) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "the mine count was given both in --dimensions and by --mines or --difficulty-level",
+            )
+            .exit();
+    }
+    fn get_settings(args: &Args) -> Option<Result<GameSettings, minesweeper::MinesweeperError>> {
+        if let Some(settings) = &args.code {
+            let valid = settings.validate_up_to(args.max_board_size);
+            return Some(valid.map(|_| settings.clone()));
+        }
+        let (width, height, mines) = if let (Some(width), Some(height)) = (args.width, args.height)
+        {
+            (width, height, args.mines)
         } else {
-            return None;
+            let dimensions = args.dimensions?;
+            (
+                dimensions.width,
+                dimensions.height,
+                dimensions.mines.or(args.mines),
+            )
         };
-        Some(GameSettings {
-            width,
-            height,
-            mines: args.mines.unwrap(),
+        let suggested = || Some(cli::suggest_mines(width, height, args.difficulty_level?));
+        let mines = mines.or_else(suggested)?;
+        let settings = GameSettings::builder()
+            .width(width)
+            .height(height)
+            .mines(mines)
+            .seed(args.seed)
+            .max_tiles(args.max_board_size);
+        Some(settings.build())
+    }
+    match args.color {
+        ColorChoice::Auto => (),
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+    }
+    let settings = get_settings(&args).map(|settings| {
+        settings.unwrap_or_else(|error| {
+            Args::command()
+                .error(ErrorKind::ValueValidation, error)
+                .exit()
         })
+    });
+    if let (Some(settings), Some(start)) = (&settings, args.start) {
+        let (x, y) = start.tile(settings.width, settings.height);
+        if x >= settings.width || y >= settings.height {
+            Args::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!("the --start tile {},{} is off the board", x + 1, y + 1),
+                )
+                .exit();
+        }
     }
-    let settings = get_settings(&args);
-    cli::begin(settings);
+    let options = cli::Options {
+        json: args.json,
+        letters: args.letters,
+        cursor: args.cursor,
+        mouse: args.mouse,
+        quiet: args.quiet || args.script.is_some() || args.a11y,
+        seed: args.seed,
+        strict_flags: args.strict_flags,
+        default_action: args.default_action,
+        redraw: args.redraw,
+        a11y: args.a11y,
+        force_width: args.force_width,
+        script: args.script,
+        force_size: args.force_size,
+        timeout: args.timeout.map(Duration::from_secs),
+        cell_width: match (args.wide, args.compact) {
+            (true, _) => 2,
+            (false, true) => 1,
+            (false, false) => args.cell_width,
+        },
+        compact: args.compact,
+        lives: args.lives,
+        allow_first_mine: args.allow_first_mine,
+        fixed_layout: args.fixed_layout,
+        difficulty: args.difficulty_level,
+        theme: args.theme,
+        labels: args.labels,
+        show_probabilities: args.show_probabilities,
+        start: args.start,
+        max_board_size: args.max_board_size,
+        confirm_quit: args.confirm_quit,
+    };
+    cli::begin(settings, options);
 }
 
-fn parse_dimensions(s: &str) -> Result<[usize; 2], String> {
-    let mut dimension: [usize; 2] = [0, 0];
-    let mut atr_itr = s.split('x');
-    for a in dimension.iter_mut() {
-        if let Some(str) = atr_itr.next() {
-            if let Ok(num) = str.parse::<usize>() {
-                *a = num;
-            } else {
-                return Err("Each dimension must be a number.".into());
-            }
+fn parse_dimensions(s: &str) -> Result<Dimensions, String> {
+    let fields: Vec<&str> = s.split('x').collect();
+    if fields.len() > 3 {
+        return Err("Expected at most three fields, WIDTHxHEIGHTxMINES.".into());
+    }
+    let mut dimension: [usize; 3] = [0, 0, 0];
+    for (a, str) in dimension.iter_mut().zip(&fields) {
+        if let Ok(num) = str.parse::<usize>() {
+            *a = num;
+        } else {
+            return Err("Each dimension must be a number.".into());
         }
     }
     if dimension[0] == 0 || dimension[1] == 0 {
         return Err("Two dimensions separated by an `x` are required.".into());
     }
+    if fields.len() == 3 && dimension[2] == 0 {
+        return Err("The mine count must be more than zero.".into());
+    }
 
-    Ok(dimension)
+    Ok(Dimensions {
+        width: dimension[0],
+        height: dimension[1],
+        mines: (fields.len() == 3).then_some(dimension[2]),
+    })
+}
+
+fn parse_code(s: &str) -> Result<GameSettings, String> {
+    GameSettings::from_code(s).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimensions_can_include_the_mines() {
+        let dimensions = parse_dimensions("10x10").unwrap();
+        assert_eq!((dimensions.width, dimensions.height), (10, 10));
+        assert_eq!(dimensions.mines, None);
+        let dimensions = parse_dimensions("10x8x15").unwrap();
+        assert_eq!((dimensions.width, dimensions.height), (10, 8));
+        assert_eq!(dimensions.mines, Some(15));
+    }
+
+    #[test]
+    fn bad_dimensions_are_refused() {
+        for s in [
+            "10",
+            "0x10",
+            "10x0",
+            "10x10x0",
+            "10x10x15x1",
+            "ax10",
+            "10x10x",
+        ] {
+            assert!(parse_dimensions(s).is_err(), "{}", s);
+        }
+    }
 }
@@ -1,7 +1,11 @@
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, VecDeque};
 use std::error::Error;
+use std::fmt;
 use std::time;
 use std::vec;
 
@@ -15,17 +19,6 @@ const SCAN: [(isize, isize); 8] = [
     (0, 1),
     (1, 1),
 ];
-const SAFE_ZONE: [(isize, isize); 9] = [
-    (-1, -1),
-    (0, -1),
-    (1, -1),
-    (-1, 0),
-    (0, 0),
-    (1, 0),
-    (-1, 1),
-    (0, 1),
-    (1, 1),
-];
 #[derive(Copy, Clone, PartialEq)]
 
 pub enum TileState {
@@ -40,13 +33,81 @@ pub enum TileState {
     Eight,
     Mine,
 }
+
+impl TileState {
+    /// The state of a tile with `count` mines around it.
+    ///
+    /// # Panics
+    ///
+    /// If `count` is more than 8, as a tile has at most eight neighbors.
+    pub fn from_count(count: u8) -> TileState {
+        use TileState::*;
+        match [Zero, One, Two, Three, Four, Five, Six, Seven, Eight].get(usize::from(count)) {
+            Some(&state) => state,
+            None => panic!("a tile can't have {} mines around it", count),
+        }
+    }
+    /// How many mines are around a tile in this state, `None` for a mine.
+    pub fn adjacent_mines(&self) -> Option<u8> {
+        match self {
+            TileState::Mine => None,
+            // The variants from Zero to Eight are in order
+            &state => Some(state as u8),
+        }
+    }
+}
+
+/// Tile states are written as the number of mines around the tile, or
+/// `"mine"`.
+#[cfg(feature = "serde")]
+impl Serialize for TileState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.adjacent_mines() {
+            Some(count) => serializer.serialize_u8(count),
+            None => serializer.serialize_str("mine"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TileState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TileState, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Written {
+            Count(usize),
+            Name(String),
+        }
+        match Written::deserialize(deserializer)? {
+            Written::Count(count @ 0..=8) => Ok(TileState::from_count(count as u8)),
+            Written::Count(count) => Err(de::Error::custom(format!(
+                "a tile can't have {} mines around it",
+                count
+            ))),
+            Written::Name(name) if name == "mine" => Ok(TileState::Mine),
+            Written::Name(name) => Err(de::Error::custom(format!("'{}' isn't a tile state", name))),
+        }
+    }
+}
+
+impl fmt::Display for TileState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.adjacent_mines() {
+            Some(count) => write!(f, "{}", count),
+            None => f.write_str("mine"),
+        }
+    }
+}
 #[derive(Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TileModifier {
     Flagged,
-    _Unsure,
+    /// Question mark, a reminder which doesn't affect play
+    Unsure,
 }
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Tile {
     pub state: TileState,
     pub modifier: Option<TileModifier>,
@@ -54,53 +115,500 @@ pub struct Tile {
     safe: bool,
 }
 
-#[derive(PartialEq)]
+impl Tile {
+    /// Whether there's a mine under the tile.
+    pub fn is_mine(&self) -> bool {
+        self.state == TileState::Mine
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GameState {
     Empty,
     Playing,
+    /// Playing, but no actions are accepted until the game is resumed
+    Paused,
     GameOver,
     Victory,
 }
+
+impl GameState {
+    /// Whether the game has been won or lost, after which nothing changes.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, GameState::GameOver | GameState::Victory)
+    }
+}
+
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            GameState::Empty => "empty",
+            GameState::Playing => "playing",
+            GameState::Paused => "paused",
+            GameState::GameOver => "game over",
+            GameState::Victory => "victory",
+        };
+        f.write_str(name)
+    }
+}
+/// Why a move was turned down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rejection {
+    /// Flags and marks can only go on the board once the first tile is swept
+    NotStarted,
+    Paused,
+    Flagged,
+    Swept,
+    /// There are already as many flags as mines, with `strict_flags`
+    NoFlagsLeft,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Rejection::NotStarted => "Sweep a tile to start the game first",
+            Rejection::Paused => "The game is paused",
+            Rejection::Flagged => "That tile is flagged, unflag it first",
+            Rejection::Swept => "That tile is already swept",
+            Rejection::NoFlagsLeft => "Every flag is placed, remove one first",
+        };
+        f.write_str(reason)
+    }
+}
+
 pub enum GameEvent {
     RevealMine(usize, usize, Tile),
     RevealTile(usize, usize, Tile),
     FlagTile(usize, usize, Tile),
+    /// A move which had no effect, and why
+    Rejected {
+        x: usize,
+        y: usize,
+        reason: Rejection,
+    },
+    QuestionTile(usize, usize, Tile),
     SweepDone,
     SweepBegin,
     InitDone,
     FlagAllMines,
     GameStart,
     GameEnd(GameBoard),
+    /// The time limit ran out, ending the game
+    TimeUp,
+    /// A mine was hit with lives to spare, leaving this many
+    LifeLost(usize),
+    Pause,
+    Resume,
 }
+/// Why a game couldn't be set up.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MinesweeperError {
+    /// The board is no tiles wide
+    ZeroWidth,
+    /// The board is no tiles tall
+    ZeroHeight,
+    /// At least one tile has to be free of mines, so there can be at most
+    /// one mine fewer than tiles
+    TooManyMines { max: usize, requested: usize },
+    /// The board has more tiles than allowed, see [`DEFAULT_MAX_TILES`]
+    BoardTooLarge { max: usize, requested: usize },
+    /// A tile, such as a mine given for [`Minesweeper::from_layout`] or one
+    /// to move on, isn't on the board
+    OutOfBounds { x: usize, y: usize },
+    /// The same mine was given twice for [`Minesweeper::from_layout`]
+    DuplicateMine { x: usize, y: usize },
+    /// A board code with a character which can't appear in one
+    InvalidCodeCharacter(char),
+    /// A board code which doesn't decode, most likely mistyped
+    MalformedCode,
+    /// A saved board whose tiles don't match its size or mine count
+    InconsistentBoard,
+}
+
+impl fmt::Display for MinesweeperError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MinesweeperError::ZeroWidth => write!(f, "The board has to be at least one tile wide"),
+            MinesweeperError::ZeroHeight => write!(f, "The board has to be at least one tile tall"),
+            MinesweeperError::TooManyMines { max, requested } => write!(
+                f,
+                "Not enough space for {} mines, at most {} fit",
+                requested, max
+            ),
+            MinesweeperError::BoardTooLarge { max, requested } => write!(
+                f,
+                "A board of {} tiles is too large, at most {} are allowed",
+                requested, max
+            ),
+            MinesweeperError::OutOfBounds { x, y } => {
+                write!(f, "{},{} is off the board", x + 1, y + 1)
+            }
+            MinesweeperError::DuplicateMine { x, y } => {
+                write!(f, "The mine at {},{} is given twice", x + 1, y + 1)
+            }
+            MinesweeperError::InvalidCodeCharacter(c) => {
+                write!(f, "'{}' can't appear in a board code", c)
+            }
+            MinesweeperError::MalformedCode => write!(f, "Malformed board code"),
+            MinesweeperError::InconsistentBoard => {
+                write!(f, "The board's tiles don't match its size and mines")
+            }
+        }
+    }
+}
+
+impl Error for MinesweeperError {}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GameSettings {
     pub width: usize,
     pub height: usize,
     pub mines: usize,
+    /// Seed for placing the mines, random if not given
+    pub seed: Option<u64>,
+}
+
+/// The most tiles a board can have unless told otherwise, so that a typo
+/// doesn't try to allocate more memory than there is.
+pub const DEFAULT_MAX_TILES: usize = 1_000_000;
+
+/// Builds [`GameSettings`], refusing settings no board can be made from.
+///
+/// ```
+/// use minesweeper_cli::prelude::*;
+///
+/// let settings = GameSettings::builder().width(30).height(16).mines(99).build();
+/// assert_eq!(settings.unwrap().mines, 99);
+///
+/// let settings = GameSettings::builder().width(3).height(3).mines(9).build();
+/// let error = MinesweeperError::TooManyMines { max: 8, requested: 9 };
+/// assert_eq!(settings.unwrap_err(), error);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct GameSettingsBuilder {
+    settings: GameSettings,
+    max_tiles: Option<usize>,
+}
+
+impl GameSettingsBuilder {
+    pub fn width(mut self, width: usize) -> Self {
+        self.settings.width = width;
+        self
+    }
+    pub fn height(mut self, height: usize) -> Self {
+        self.settings.height = height;
+        self
+    }
+    pub fn mines(mut self, mines: usize) -> Self {
+        self.settings.mines = mines;
+        self
+    }
+    /// Seed for placing the mines, random if not given or `None`
+    pub fn seed(mut self, seed: impl Into<Option<u64>>) -> Self {
+        self.settings.seed = seed.into();
+        self
+    }
+    /// Allow boards of up to this many tiles, [`DEFAULT_MAX_TILES`] if not
+    /// given
+    pub fn max_tiles(mut self, max_tiles: usize) -> Self {
+        self.max_tiles = Some(max_tiles);
+        self
+    }
+    pub fn build(self) -> Result<GameSettings, MinesweeperError> {
+        let max_tiles = self.max_tiles.unwrap_or(DEFAULT_MAX_TILES);
+        self.settings.validate_up_to(max_tiles)?;
+        Ok(self.settings)
+    }
+}
+
+/// Crockford's base32 alphabet, which leaves out I, L, O and U.
+const CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+impl GameSettings {
+    /// The classic 9x9 board with 10 mines.
+    pub const fn beginner() -> GameSettings {
+        GameSettings::classic(9, 9, 10)
+    }
+    /// The classic 16x16 board with 40 mines.
+    pub const fn intermediate() -> GameSettings {
+        GameSettings::classic(16, 16, 40)
+    }
+    /// The classic 30x16 board with 99 mines.
+    pub const fn expert() -> GameSettings {
+        GameSettings::classic(30, 16, 99)
+    }
+    const fn classic(width: usize, height: usize, mines: usize) -> GameSettings {
+        GameSettings {
+            width,
+            height,
+            mines,
+            seed: None,
+        }
+    }
+    /// Settings checked as they're built, see [`GameSettingsBuilder`].
+    pub fn builder() -> GameSettingsBuilder {
+        GameSettingsBuilder::default()
+    }
+    /// Checks that a board can be generated from these settings, with at
+    /// most [`DEFAULT_MAX_TILES`] tiles.
+    pub fn validate(&self) -> Result<(), MinesweeperError> {
+        self.validate_up_to(DEFAULT_MAX_TILES)
+    }
+    /// Like [`GameSettings::validate`], allowing up to `max_tiles` tiles.
+    pub fn validate_up_to(&self, max_tiles: usize) -> Result<(), MinesweeperError> {
+        if self.width == 0 {
+            return Err(MinesweeperError::ZeroWidth);
+        }
+        if self.height == 0 {
+            return Err(MinesweeperError::ZeroHeight);
+        }
+        let tiles = self.width.saturating_mul(self.height);
+        if tiles > max_tiles {
+            return Err(MinesweeperError::BoardTooLarge {
+                max: max_tiles,
+                requested: tiles,
+            });
+        }
+        // At least the first swept tile has to be free of mines. Else, the
+        // loop that places mines on board will never complete.
+        let max = tiles - 1;
+        if self.mines > max {
+            return Err(MinesweeperError::TooManyMines {
+                max,
+                requested: self.mines,
+            });
+        }
+        Ok(())
+    }
+    /// Packs the seed, dimensions and mine count into a short code which
+    /// `from_code` turns back into the same settings. Unseeded settings have
+    /// no code, as their board can't be reproduced.
+    pub fn to_code(&self) -> Option<String> {
+        let mut bytes = vec![];
+        for value in [
+            self.seed?,
+            self.width as u64,
+            self.height as u64,
+            self.mines as u64,
+        ] {
+            // LEB128, seven bits per byte with the high bit set on all but
+            // the last byte
+            let mut value = value;
+            while value >= 0x80 {
+                bytes.push(value as u8 | 0x80);
+                value >>= 7;
+            }
+            bytes.push(value as u8);
+        }
+        bytes.push(checksum(&bytes));
+
+        let mut code = String::new();
+        let mut buffer = 0u16;
+        let mut bits = 0;
+        for byte in bytes {
+            buffer = buffer << 8 | byte as u16;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                code.push(CODE_ALPHABET[(buffer >> bits) as usize & 31] as char);
+            }
+        }
+        if bits > 0 {
+            code.push(CODE_ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+        }
+        Some(code)
+    }
+    /// Reads settings back from a code made by `to_code`. The settings
+    /// aren't checked, as how big a board may be is up to the caller; see
+    /// [`GameSettings::validate_up_to`].
+    pub fn from_code(code: &str) -> Result<GameSettings, MinesweeperError> {
+        let mut bytes = vec![];
+        let mut buffer = 0u16;
+        let mut bits = 0;
+        for c in code.trim().chars() {
+            let Some(digit) = CODE_ALPHABET
+                .iter()
+                .position(|&d| d as char == c.to_ascii_uppercase())
+            else {
+                return Err(MinesweeperError::InvalidCodeCharacter(c));
+            };
+            buffer = buffer << 5 | digit as u16;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((buffer >> bits) as u8);
+            }
+        }
+        let malformed = || MinesweeperError::MalformedCode;
+        let (&check, bytes) = bytes.split_last().ok_or_else(malformed)?;
+        if check != checksum(bytes) {
+            return Err(malformed());
+        }
+
+        let mut values = [0u64; 4];
+        let mut bytes = bytes.iter();
+        for value in values.iter_mut() {
+            let mut shift = 0;
+            loop {
+                let byte = *bytes.next().ok_or_else(malformed)?;
+                if shift >= 64 {
+                    return Err(malformed());
+                }
+                *value |= ((byte & 0x7f) as u64) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+        if bytes.next().is_some() {
+            return Err(malformed());
+        }
+        let [seed, width, height, mines] = values;
+        let settings = GameSettings {
+            width: width.try_into().map_err(|_| malformed())?,
+            height: height.try_into().map_err(|_| malformed())?,
+            mines: mines.try_into().map_err(|_| malformed())?,
+            seed: Some(seed),
+        };
+        Ok(settings)
+    }
+}
+
+/// Catches typos in board codes.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, &byte| sum.rotate_left(1) ^ byte)
+}
+
+/// Most moves kept in `Minesweeper::history`, older ones are dropped.
+const HISTORY_LIMIT: usize = 1000;
+
+#[derive(Clone, Copy)]
+pub enum Move {
+    Sweep(usize, usize),
+    Flag(usize, usize),
+    Question(usize, usize),
+    Chord(usize, usize),
+}
+
+impl Move {
+    /// The tile the move was made on.
+    pub fn position(&self) -> (usize, usize) {
+        match *self {
+            Move::Sweep(x, y) | Move::Flag(x, y) | Move::Question(x, y) | Move::Chord(x, y) => {
+                (x, y)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (name, x, y) = match *self {
+            Move::Sweep(x, y) => ("sweep", x, y),
+            Move::Flag(x, y) => ("flag", x, y),
+            Move::Question(x, y) => ("mark", x, y),
+            Move::Chord(x, y) => ("chord", x, y),
+        };
+        write!(f, "{} {},{}", name, x + 1, y + 1)
+    }
+}
+
+pub struct HistoryEntry {
+    pub action: Move,
+    /// What the move did, e.g. "revealed 12 tiles"
+    pub outcome: String,
 }
 
 pub struct Minesweeper {
     pub board: GameBoard,
     pub state: GameState,
-    pub start_time: Option<time::Instant>,
+    /// When the clock last started: at the first sweep, then on each resume.
+    /// `None` while it's stopped
+    start_time: Option<time::Instant>,
+    /// Time played before `start_time`
+    played: time::Duration,
+    /// Moves made this game, including those which had no effect
+    pub moves: usize,
+    pub seed: Option<u64>,
+    /// Refuse to place more flags than there are mines
+    pub strict_flags: bool,
+    /// Place mines anywhere, even under the first tile swept. Only for
+    /// testing, normal games always start by opening up an area.
+    pub allow_first_mine: bool,
+    /// Place the mines from the seed alone, so that a seeded board is the
+    /// same wherever it's first swept. A mine under the first sweep is moved
+    /// instead, so the first sweep can land on a number rather than opening
+    /// up an area.
+    pub fixed_layout: bool,
+    /// Mines which can be hit before the game is lost, one by default
+    pub lives: usize,
+    /// Lives lost this game, given back when it's reset
+    lives_lost: usize,
+    /// End the game once this long has passed since the first sweep
+    pub timeout: Option<time::Duration>,
+    /// The mine which ended the game
+    pub detonated: Option<(usize, usize)>,
+    /// Moves made this game, oldest first
+    pub history: VecDeque<HistoryEntry>,
     pub events: Events,
 }
+/// Listens to every event as it happens, see [`Minesweeper::subscribe`].
+type Listener = Box<dyn FnMut(&GameEvent)>;
+
 pub struct Events {
-    events: Vec<GameEvent>,
+    events: VecDeque<GameEvent>,
+    listeners: Vec<Listener>,
 }
 
 impl Events {
     fn add(&mut self, event: GameEvent) {
-        self.events.push(event);
+        for listener in &mut self.listeners {
+            listener(&event);
+        }
+        self.events.push_back(event);
     }
+    /// Takes the oldest event not yet handled, so events come out in the
+    /// order they happened.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<GameEvent> {
-        if self.events.len() > 0 {
-            self.events.pop()
-        } else {
-            None
-        }
+        self.events.pop_front()
+    }
+    /// Takes every event not yet handled at once, oldest first, for front
+    /// ends which catch up once per frame.
+    ///
+    /// ```
+    /// use minesweeper_cli::prelude::*;
+    ///
+    /// let mut game = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+    /// game.sweep(0, 0).unwrap();
+    /// let pending = game.events.len();
+    /// let events = game.events.drain();
+    /// assert_eq!(events.len(), pending);
+    /// assert!(matches!(events[0], GameEvent::RevealTile(0, 0, _)));
+    /// assert!(game.events.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        self.events.drain(..).collect()
+    }
+    /// How many events are waiting to be handled.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
     }
 }
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "SavedBoard")
+)]
 pub struct GameBoard {
     pub tiles: Vec<Vec<Tile>>,
     pub width: usize,
@@ -108,18 +616,333 @@ pub struct GameBoard {
     pub mines: usize,
     pub flags: usize,
     valid_flags: usize,
+    /// Tiles without a mine which have been swept
+    cleared: usize,
+    /// Every tile swept, including a mine which ended the game
+    swept: usize,
 }
 
-impl Minesweeper {
-    pub fn new(settings: &GameSettings) -> Result<Minesweeper, Box<dyn Error>> {
-        if settings.width == 0 || settings.height == 0 {
-            return Err("Can't make game board with zero length dimension".into());
+/// A board as it's read back, before it's checked. The counters aren't
+/// read, they're counted again from the tiles.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct SavedBoard {
+    tiles: Vec<Vec<Tile>>,
+    width: usize,
+    height: usize,
+    mines: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SavedBoard> for GameBoard {
+    type Error = MinesweeperError;
+
+    fn try_from(saved: SavedBoard) -> Result<GameBoard, MinesweeperError> {
+        let SavedBoard {
+            tiles,
+            width,
+            height,
+            mines,
+        } = saved;
+        let settings = GameSettings {
+            width,
+            height,
+            mines,
+            seed: None,
         };
-        if (settings.width * settings.height) as isize - 9 < settings.mines as isize {
-            // Can't have more mines than tiles on the game board. Else, the
-            // loop that places mines on board will never complete.
-            return Err("Not enough space for mines".into());
+        settings.validate_up_to(usize::MAX)?;
+        if tiles.len() != width || tiles.iter().any(|column| column.len() != height) {
+            return Err(MinesweeperError::InconsistentBoard);
+        }
+        let (mut placed, mut flags, mut valid_flags, mut cleared, mut swept) = (0, 0, 0, 0, 0);
+        for tile in tiles.iter().flatten() {
+            let flagged = tile.modifier == Some(TileModifier::Flagged);
+            placed += tile.is_mine() as usize;
+            flags += flagged as usize;
+            valid_flags += (flagged && tile.is_mine()) as usize;
+            swept += tile.swept as usize;
+            cleared += (tile.swept && !tile.is_mine()) as usize;
         }
+        // The mines are only placed by the first sweep
+        if placed != mines && (placed != 0 || swept != 0) {
+            return Err(MinesweeperError::InconsistentBoard);
+        }
+        Ok(GameBoard {
+            tiles,
+            width,
+            height,
+            mines,
+            flags,
+            valid_flags,
+            cleared,
+            swept,
+        })
+    }
+}
+
+impl GameBoard {
+    /// The tiles kept clear of mines when the first sweep is at `(x, y)`: the
+    /// tile itself, and as many of its neighbors as leave room for the mines.
+    pub fn safe_zone(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let spare_tiles = self.width * self.height - 1 - self.mines;
+        std::iter::once((x, y))
+            .chain(self.neighbors(x, y).take(spare_tiles))
+            .collect()
+    }
+    /// The share of tiles with a mine, from 0 to 1.
+    pub fn density(&self) -> f64 {
+        self.mines as f64 / (self.width * self.height) as f64
+    }
+    /// Mines minus flags placed, negative once there are too many flags.
+    pub fn mines_left(&self) -> isize {
+        self.mines as isize - self.flags as isize
+    }
+    /// Tiles swept so far, including the mine which lost the game if one
+    /// was swept.
+    pub fn swept_count(&self) -> usize {
+        self.swept
+    }
+    /// Tiles without a mine which are yet to be swept. Flagged tiles count
+    /// too, flagging doesn't sweep them.
+    pub fn unswept_safe_count(&self) -> usize {
+        self.width * self.height - self.mines - self.cleared
+    }
+    /// How much of the board has been cleared, as a share of the tiles
+    /// without a mine from 0 to 1.
+    pub fn cleared_fraction(&self) -> f64 {
+        let safe_tiles = self.width * self.height - self.mines;
+        self.cleared as f64 / safe_tiles as f64
+    }
+    /// Tiles without a mine which are yet to be swept.
+    #[deprecated(since = "0.1.0", note = "renamed to `unswept_safe_count`")]
+    pub fn remaining_safe_tiles(&self) -> usize {
+        self.unswept_safe_count()
+    }
+    /// How much of the board has been cleared, as a percentage of the tiles
+    /// without a mine.
+    #[deprecated(since = "0.1.0", note = "use `cleared_fraction` and multiply by 100")]
+    pub fn cleared_percent(&self) -> f64 {
+        self.cleared_fraction() * 100.0
+    }
+    /// Hidden tiles next to at least one swept number, the ones the numbers
+    /// say something about. In the same order as [`GameBoard::iter`].
+    pub fn frontier(&self) -> Vec<(usize, usize)> {
+        self.iter()
+            .filter(|&(x, y, tile)| {
+                !tile.swept
+                    && self.neighbors(x, y).any(|(x, y)| {
+                        self.get(x, y).is_some_and(|neighbor| {
+                            neighbor.swept && neighbor.state.adjacent_mines().is_some_and(|n| n > 0)
+                        })
+                    })
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+    /// The fewest clicks the board can be cleared in, its "3BV": one for
+    /// each opening of connected zeros, which sweeps its border too, and one
+    /// for each numbered tile not on the border of an opening.
+    pub fn three_bv(&self) -> usize {
+        let mut counted = vec![vec![false; self.height]; self.width];
+        let mut clicks = 0;
+        for (x, y, tile) in self.iter() {
+            if counted[x][y] || tile.state != TileState::Zero {
+                continue;
+            }
+            clicks += 1;
+            counted[x][y] = true;
+            let mut opening = vec![(x, y)];
+            while let Some((x, y)) = opening.pop() {
+                for (x, y) in self.neighbors(x, y) {
+                    if !counted[x][y] {
+                        counted[x][y] = true;
+                        if self.tiles[x][y].state == TileState::Zero {
+                            opening.push((x, y));
+                        }
+                    }
+                }
+            }
+        }
+        let numbers = self
+            .iter()
+            .filter(|&(x, y, tile)| !counted[x][y] && !tile.is_mine())
+            .count();
+        clicks + numbers
+    }
+    /// A hash of the board's size and where its mines are, for checking that
+    /// two games were played on the same board. Sweeping and flagging don't
+    /// change it, and it's the same on every platform and version, as it's
+    /// 64-bit FNV-1a rather than the standard library's hasher. Only
+    /// meaningful once the mines are placed.
+    ///
+    /// ```
+    /// use minesweeper_cli::prelude::*;
+    ///
+    /// let mut game = Minesweeper::from_layout(4, 4, &[(3, 3)]).unwrap();
+    /// let fingerprint = game.board.fingerprint();
+    /// game.flag(3, 3).unwrap();
+    /// game.sweep(0, 0).unwrap();
+    /// assert_eq!(game.board.fingerprint(), fingerprint);
+    ///
+    /// let other = Minesweeper::from_layout(4, 4, &[(3, 2)]).unwrap();
+    /// assert_ne!(other.board.fingerprint(), fingerprint);
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        let mines = self.iter().map(|(_, _, tile)| tile.is_mine() as u8);
+        (self.width as u64)
+            .to_le_bytes()
+            .into_iter()
+            .chain((self.height as u64).to_le_bytes())
+            .chain(mines)
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(PRIME)
+            })
+    }
+    /// Coordinates of every flagged tile, row by row from the top.
+    pub fn flag_positions(&self) -> Vec<(usize, usize)> {
+        self.positions(|tile| tile.modifier == Some(TileModifier::Flagged))
+    }
+    /// Coordinates of every mine, row by row from the top. Only built with the
+    /// `debug-introspection` feature, for solvers and tests, so that the
+    /// game itself can't give the mines away.
+    #[cfg(feature = "debug-introspection")]
+    pub fn mine_positions(&self) -> Vec<(usize, usize)> {
+        self.positions(|tile| tile.is_mine())
+    }
+    fn positions(&self, matches: impl Fn(&Tile) -> bool) -> Vec<(usize, usize)> {
+        self.iter()
+            .filter(|(_, _, tile)| matches(tile))
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+    /// Every tile with its coordinates, row by row from the top and each
+    /// row from the left, the order the board is read in.
+    ///
+    /// ```
+    /// use minesweeper_cli::prelude::*;
+    ///
+    /// let game = Minesweeper::from_layout(2, 2, &[(0, 0)]).unwrap();
+    /// let order: Vec<_> = game.board.iter().map(|(x, y, _)| (x, y)).collect();
+    /// assert_eq!(order, [(0, 0), (1, 0), (0, 1), (1, 1)]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &Tile)> {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y, &self.tiles[x][y])))
+    }
+    /// Numbers every tile without a mine with the mines next to it, once
+    /// the mines are placed.
+    fn count_mines(&mut self) {
+        for (x, y) in self.positions(|tile| tile.is_mine()) {
+            for (new_x, new_y) in self.neighbors(x, y) {
+                let tile = &mut self.tiles[new_x][new_y];
+                if let Some(count) = tile.state.adjacent_mines() {
+                    tile.state = TileState::from_count(count + 1);
+                }
+            }
+        }
+    }
+    /// Like [`GameBoard::iter`], but the tiles can be changed.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut Tile)> {
+        // Each row takes the next tile from every column in turn
+        let width = self.width;
+        let mut columns: Vec<_> = self
+            .tiles
+            .iter_mut()
+            .map(|column| column.iter_mut())
+            .collect();
+        let mut next = 0;
+        std::iter::from_fn(move || {
+            let (x, y) = (next % width, next / width);
+            let tile = columns.get_mut(x)?.next()?;
+            next += 1;
+            Some((x, y, tile))
+        })
+    }
+    /// The board without colors or borders, one character per tile and one
+    /// line per row: `#` hidden, `F` flagged, `?` unsure, a space for zero,
+    /// the number of neighboring mines, or `*` for a swept mine. With
+    /// `reveal`, mines are shown whether or not they were swept.
+    pub fn to_ascii_grid(&self, reveal: bool) -> String {
+        let rows: Vec<String> = (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        let tile = &self.tiles[x][y];
+                        if tile.is_mine() && (tile.swept || reveal) {
+                            return '*';
+                        }
+                        match (tile.swept, tile.modifier) {
+                            (false, Some(TileModifier::Flagged)) => 'F',
+                            (false, Some(TileModifier::Unsure)) => '?',
+                            (false, None) => '#',
+                            (true, _) => match tile.state.adjacent_mines() {
+                                Some(0) => ' ',
+                                Some(count) => {
+                                    char::from_digit(count.into(), 10).expect("at most eight")
+                                }
+                                None => '*',
+                            },
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        rows.join("\n")
+    }
+    /// Coordinates of the tiles adjacent to `(x, y)` which lie on the board,
+    /// so fewer than eight at the edges, and as few as one on a board only a
+    /// tile wide. Everything which looks around a tile goes through this.
+    pub fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+        SCAN.iter().filter_map(move |&(dx, dy)| {
+            let x = x.checked_add_signed(dx)?;
+            let y = y.checked_add_signed(dy)?;
+            (x < width && y < height).then_some((x, y))
+        })
+    }
+    /// Whether `(x, y)` lies on the board.
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+    /// The tile at `(x, y)`, or `None` if that's off the board.
+    pub fn get(&self, x: usize, y: usize) -> Option<&Tile> {
+        self.tiles.get(x)?.get(y)
+    }
+    /// Like [`GameBoard::get`], but the tile can be changed.
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Tile> {
+        self.tiles.get_mut(x)?.get_mut(y)
+    }
+}
+
+impl Minesweeper {
+    /// Sets up a game, refusing boards of more than [`DEFAULT_MAX_TILES`]
+    /// tiles.
+    ///
+    /// ```
+    /// use minesweeper_cli::prelude::*;
+    ///
+    /// let settings = GameSettings {
+    ///     width: 100_000,
+    ///     height: 100_000,
+    ///     mines: 10,
+    ///     seed: None,
+    /// };
+    /// let error = MinesweeperError::BoardTooLarge {
+    ///     max: 1_000_000,
+    ///     requested: 10_000_000_000,
+    /// };
+    /// assert_eq!(Minesweeper::new(&settings).err(), Some(error));
+    /// ```
+    pub fn new(settings: &GameSettings) -> Result<Minesweeper, MinesweeperError> {
+        Minesweeper::with_max_tiles(settings, DEFAULT_MAX_TILES)
+    }
+    /// Like [`Minesweeper::new`], allowing boards of up to `max_tiles` tiles.
+    pub fn with_max_tiles(
+        settings: &GameSettings,
+        max_tiles: usize,
+    ) -> Result<Minesweeper, MinesweeperError> {
+        settings.validate_up_to(max_tiles)?;
 
         let board = GameBoard {
             tiles: vec![
@@ -139,45 +962,265 @@ impl Minesweeper {
             mines: settings.mines,
             flags: 0,
             valid_flags: 0,
+            cleared: 0,
+            swept: 0,
         };
         let game = Self {
             board,
             state: GameState::Empty,
             start_time: None,
-            events: Events { events: vec![] },
+            played: time::Duration::ZERO,
+            moves: 0,
+            seed: settings.seed,
+            strict_flags: false,
+            timeout: None,
+            lives: 1,
+            lives_lost: 0,
+            allow_first_mine: false,
+            fixed_layout: false,
+            detonated: None,
+            events: Events {
+                events: VecDeque::new(),
+                listeners: Vec::new(),
+            },
+            history: VecDeque::new(),
         };
 
         Ok(game)
     }
+    /// A game with mines on exactly the given tiles, for puzzles and tests.
+    /// It starts straight away, so there's no safe first sweep.
+    ///
+    /// ```
+    /// use minesweeper_cli::prelude::*;
+    ///
+    /// let mut game = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+    /// game.sweep(0, 0).unwrap();
+    /// assert!(game.state == GameState::Victory);
+    /// ```
+    pub fn from_layout(
+        width: usize,
+        height: usize,
+        mines: &[(usize, usize)],
+    ) -> Result<Minesweeper, MinesweeperError> {
+        let mut game = Minesweeper::new(&GameSettings {
+            width,
+            height,
+            mines: mines.len(),
+            seed: None,
+        })?;
+        for &(x, y) in mines {
+            let tile = game
+                .board
+                .get_mut(x, y)
+                .ok_or(MinesweeperError::OutOfBounds { x, y })?;
+            if tile.is_mine() {
+                return Err(MinesweeperError::DuplicateMine { x, y });
+            }
+            tile.state = TileState::Mine;
+        }
+        game.board.count_mines();
+        game.start_time = Some(time::Instant::now());
+        game.state = GameState::Playing;
+        Ok(game)
+    }
+    /// Clears the board for a new game of the same size and mine count. The
+    /// mines are placed again on the next sweep, from `seed` if there is one.
+    /// Options such as `lives` and `timeout` are kept, and so are listeners.
+    ///
+    /// ```
+    /// use minesweeper_cli::prelude::*;
+    ///
+    /// let settings = GameSettings::builder().width(9).height(9).mines(10).build();
+    /// let mut game = Minesweeper::new(&settings.unwrap()).unwrap();
+    /// game.sweep(4, 4).unwrap();
+    /// game.reset(Some(1));
+    /// assert_eq!(game.state, GameState::Empty);
+    /// assert_eq!(game.board.swept_count(), 0);
+    /// assert!(game.board.iter().all(|(_, _, tile)| !tile.is_mine()));
+    /// ```
+    pub fn reset(&mut self, seed: Option<u64>) {
+        for (_, _, tile) in self.board.iter_mut() {
+            *tile = Tile {
+                state: TileState::Zero,
+                modifier: None,
+                swept: false,
+                safe: false,
+            };
+        }
+        self.seed = seed;
+        self.restart();
+        self.state = GameState::Empty;
+    }
+    /// Starts the same board again, with the mines where they were. As with
+    /// [`Minesweeper::from_layout`], the game is under way straight away,
+    /// though the clock waits for the first move. A game which hadn't
+    /// started yet has no mines to keep, and is left as it is.
+    pub fn reset_keep_mines(&mut self) {
+        if self.state == GameState::Empty {
+            return;
+        }
+        for (_, _, tile) in self.board.iter_mut() {
+            tile.swept = false;
+            tile.modifier = None;
+            tile.safe = false;
+        }
+        self.restart();
+        self.state = GameState::Playing;
+    }
+    /// Forgets everything that happened in the game, apart from the tiles.
+    fn restart(&mut self) {
+        self.board.flags = 0;
+        self.board.valid_flags = 0;
+        self.board.cleared = 0;
+        self.board.swept = 0;
+        self.start_time = None;
+        self.played = time::Duration::ZERO;
+        self.moves = 0;
+        self.lives += self.lives_lost;
+        self.lives_lost = 0;
+        self.detonated = None;
+        self.history.clear();
+        self.events.events.clear();
+    }
+    /// Reveals a tile, placing the mines first if it's the first sweep.
+    /// Like every move, a tile off the board is an error, while a sweep
+    /// which can't be made right now is turned down with a
+    /// [`GameEvent::Rejected`]:
+    ///
+    /// ```
+    /// use minesweeper_cli::minesweeper::Rejection;
+    /// use minesweeper_cli::prelude::*;
+    ///
+    /// let mut game = Minesweeper::from_layout(3, 3, &[(0, 0)]).unwrap();
+    /// assert_eq!(
+    ///     game.sweep(3, 0),
+    ///     Err(MinesweeperError::OutOfBounds { x: 3, y: 0 })
+    /// );
+    /// game.flag(1, 1)?;
+    /// game.sweep(1, 1)?;
+    /// assert!(matches!(
+    ///     game.events.drain().last(),
+    ///     Some(GameEvent::Rejected {
+    ///         reason: Rejection::Flagged,
+    ///         ..
+    ///     })
+    /// ));
+    /// # Ok::<(), MinesweeperError>(())
+    /// ```
+    pub fn sweep(&mut self, x: usize, y: usize) -> Result<(), MinesweeperError> {
+        self.checked_move(Move::Sweep(x, y))
+    }
+    /// Flags a hidden tile, or takes its flag away.
+    pub fn flag(&mut self, x: usize, y: usize) -> Result<(), MinesweeperError> {
+        self.checked_move(Move::Flag(x, y))
+    }
+    /// Toggles a question mark on a hidden, unflagged tile.
+    pub fn question(&mut self, x: usize, y: usize) -> Result<(), MinesweeperError> {
+        self.checked_move(Move::Question(x, y))
+    }
+    /// Sweeps every hidden neighbor of a swept tile, provided the number of
+    /// flags around it matches the number of mines around it.
+    pub fn chord(&mut self, x: usize, y: usize) -> Result<(), MinesweeperError> {
+        self.checked_move(Move::Chord(x, y))
+    }
+    /// Makes a move if its tile is on the board. Moves off the board don't
+    /// count and aren't added to the history.
+    fn checked_move(&mut self, action: Move) -> Result<(), MinesweeperError> {
+        let (x, y) = action.position();
+        if !self.board.in_bounds(x, y) {
+            return Err(MinesweeperError::OutOfBounds { x, y });
+        }
+        self.make_move(action);
+        Ok(())
+    }
+    /// Makes a move on a tile on the board and adds it to the history.
+    fn make_move(&mut self, action: Move) {
+        let first_event = self.events.events.len();
+        // A replayed board is under way before its clock starts
+        if self.is_playing() && self.start_time.is_none() {
+            self.start_time = Some(time::Instant::now());
+        }
+        match action {
+            Move::Sweep(x, y) => self.sweep_tile(x, y),
+            Move::Flag(x, y) => self.flag_tile(x, y),
+            Move::Question(x, y) => self.question_tile(x, y),
+            Move::Chord(x, y) => self.chord_tile(x, y),
+        }
+        self.record(action, first_event);
+    }
+    /// The tile at `(x, y)`, if moves can be made on it right now. If not,
+    /// the move is rejected, unless the game is already over. Moves are
+    /// checked to be on the board before they get here.
+    fn movable_tile(&mut self, x: usize, y: usize) -> Option<Tile> {
+        let &tile = self.board.get(x, y)?;
+        let reason = match self.state {
+            GameState::Playing => return Some(tile),
+            GameState::Empty => Rejection::NotStarted,
+            GameState::Paused => Rejection::Paused,
+            GameState::GameOver | GameState::Victory => return None,
+        };
+        self.reject(x, y, reason);
+        None
+    }
+    fn reject(&mut self, x: usize, y: usize, reason: Rejection) {
+        self.events.add(GameEvent::Rejected { x, y, reason });
+    }
+    /// Adds a move to the history, summarising the events it produced.
+    fn record(&mut self, action: Move, first_event: usize) {
+        self.moves += 1;
+        let mut revealed = 0;
+        let mut outcome = None;
+        let mut rejection = None;
+        for event in self.events.events.range(first_event..) {
+            match event {
+                GameEvent::RevealTile(_, _, _) => revealed += 1,
+                GameEvent::RevealMine(_, _, _) => outcome = Some("hit a mine"),
+                GameEvent::FlagTile(_, _, tile) if tile.modifier.is_some() => {
+                    outcome = Some("flagged")
+                }
+                GameEvent::FlagTile(_, _, _) => outcome = Some("unflagged"),
+                GameEvent::Rejected { reason, .. } => rejection = Some(*reason),
+                GameEvent::QuestionTile(_, _, tile) if tile.modifier.is_some() => {
+                    outcome = Some("marked")
+                }
+                GameEvent::QuestionTile(_, _, _) => outcome = Some("unmarked"),
+                GameEvent::LifeLost(_) => outcome = Some("hit a mine and lost a life"),
+                _ => (),
+            }
+        }
+        let outcome = match (rejection, outcome, revealed) {
+            (Some(reason), _, _) => reason.to_string(),
+            (None, Some(outcome), _) => outcome.to_string(),
+            (None, None, 0) => "no effect".to_string(),
+            (None, None, 1) => "revealed 1 tile".to_string(),
+            (None, None, revealed) => format!("revealed {} tiles", revealed),
+        };
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(HistoryEntry { action, outcome });
+    }
+    /// Places the mines once the first tile is swept. Unless
+    /// `allow_first_mine` is set, that tile and all of its neighbors are kept
+    /// clear, so the first sweep is always a zero and opens up an area of the
+    /// board. On boards too crowded for that, only as many neighbors as leave
+    /// room for the mines are kept clear. With `fixed_layout`, only the
+    /// first tile is, see `move_first_mine`.
     fn generate(&mut self, avoid_x: usize, avoid_y: usize) {
         self.events.add(GameEvent::GameStart);
 
         let width = self.board.width;
         let height = self.board.height;
-        // Make list of all safe positions which are actually on board,
-        //removing all which are before, or past the bounds of the board.
-        let mut valid_safezone: Vec<(isize, isize)> = SAFE_ZONE.to_vec();
-        valid_safezone.retain_mut(|pos: &mut (isize, isize)| {
-            let adjusted_x = pos.0 + avoid_x as isize;
-            let adjusted_y = pos.1 + avoid_y as isize;
-            if adjusted_x >= 0
-                && adjusted_y >= 0
-                && adjusted_x < width as isize
-                && adjusted_y < height as isize
-            {
-                pos.0 = adjusted_x;
-                pos.1 = adjusted_y;
-                return true;
-            }
-            false
-        });
-        for safe_pos in valid_safezone.iter() {
-            let safe_x = safe_pos.0 as usize;
-            let safe_y = safe_pos.1 as usize;
-            self.board.tiles[safe_x][safe_y].safe = true;
+        if !self.allow_first_mine && !self.fixed_layout {
+            for (safe_x, safe_y) in self.board.safe_zone(avoid_x, avoid_y) {
+                self.board.tiles[safe_x][safe_y].safe = true;
+            }
         }
-        #[cfg(not(target_arch = "wasm32"))]
-        let mut rng = rand::thread_rng();
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
 
         let mut i = 0;
 
@@ -185,9 +1228,9 @@ impl Minesweeper {
             let x = rng.gen_range(0..width);
             let y = rng.gen_range(0..height);
 
-            let mut tile = &mut self.board.tiles[x][y];
+            let tile = &mut self.board.tiles[x][y];
 
-            if tile.state == TileState::Mine || tile.safe == true {
+            if tile.is_mine() || tile.safe {
                 continue;
             }
 
@@ -195,126 +1238,1387 @@ impl Minesweeper {
 
             i += 1;
         }
-
-        for x in 0..width {
-            for y in 0..height {
-                if self.board.tiles[x][y].state == TileState::Mine {
-                    for &scan_location in SCAN.iter() {
-                        let new_x = x as isize + scan_location.0;
-                        let new_y = y as isize + scan_location.1;
-                        if new_x >= width as isize
-                            || new_x < 0
-                            || new_y >= height as isize
-                            || new_y < 0
-                        {
-                            continue;
-                        }
-                        let new_x = new_x as usize;
-                        let new_y = new_y as usize;
-                        if self.board.tiles[new_x][new_y].state != TileState::Mine {
-                            self.board.tiles[new_x][new_y].state =
-                                match self.board.tiles[new_x][new_y].state {
-                                    TileState::Zero => TileState::One,
-                                    TileState::One => TileState::Two,
-                                    TileState::Two => TileState::Three,
-                                    TileState::Three => TileState::Four,
-                                    TileState::Four => TileState::Five,
-                                    TileState::Five => TileState::Six,
-                                    TileState::Six => TileState::Seven,
-                                    TileState::Seven => TileState::Eight,
-                                    _ => TileState::Eight,
-                                }
-                        }
-                    }
-                }
-            }
+        if self.fixed_layout && !self.allow_first_mine {
+            self.move_first_mine(avoid_x, avoid_y);
         }
+
+        self.board.count_mines();
         self.start_time = Some(time::Instant::now());
         self.state = GameState::Playing;
         self.events.add(GameEvent::InitDone);
     }
-    pub fn sweep(&mut self, x: usize, y: usize) {
-        if GameState::Empty == self.state {
+    /// Moves a mine placed under the first sweep to the first free tile,
+    /// reading row by row from the top left, as the classic game does.
+    fn move_first_mine(&mut self, x: usize, y: usize) {
+        if !self.board.tiles[x][y].is_mine() {
+            return;
+        }
+        let (width, height) = (self.board.width, self.board.height);
+        let (free_x, free_y) = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .find(|&(x, y)| !self.board.tiles[x][y].is_mine())
+            .expect("there is always a tile without a mine");
+        self.board.tiles[free_x][free_y].state = TileState::Mine;
+        self.board.tiles[x][y].state = TileState::Zero;
+    }
+    fn sweep_tile(&mut self, x: usize, y: usize) {
+        if GameState::Empty == self.state && self.board.in_bounds(x, y) {
             self.generate(x, y);
         }
-        if self.state != GameState::Playing {
+        let Some(tile) = self.movable_tile(x, y) else {
             return;
+        };
+        if tile.modifier == Some(TileModifier::Flagged) {
+            return self.reject(x, y, Rejection::Flagged);
         }
-        let &tile = &self.board.tiles[x][y];
-        if let Some(_) = tile.modifier {
-            return;
+        if tile.swept {
+            return self.reject(x, y, Rejection::Swept);
         }
-        self.board.tiles[x][y].swept = true;
-        self.events
-            .add(GameEvent::RevealTile(x, y, self.board.tiles[x][y].clone()));
+        let swept = self.board.get_mut(x, y).expect("checked by movable_tile");
+        swept.swept = true;
+        swept.modifier = None;
+        self.events.add(GameEvent::RevealTile(x, y, *swept));
+        self.board.swept += 1;
 
-        if tile.state == TileState::Mine {
-            self.events.add(GameEvent::RevealMine(x, y, tile.clone()));
+        if tile.is_mine() {
+            self.events.add(GameEvent::RevealMine(x, y, tile));
+            if self.lives > 1 {
+                // The mine is flagged so that it can't be hit again. Not
+                // through `flag_tile`, which would refuse with
+                // `strict_flags` or win with the last mine
+                self.lives -= 1;
+                self.lives_lost += 1;
+                self.board.swept -= 1;
+                let mine = self.board.get_mut(x, y).expect("checked by movable_tile");
+                mine.swept = false;
+                mine.modifier = Some(TileModifier::Flagged);
+                self.events.add(GameEvent::FlagTile(x, y, *mine));
+                self.board.flags += 1;
+                self.board.valid_flags += 1;
+                self.events.add(GameEvent::LifeLost(self.lives));
+                return;
+            }
+            self.detonated = Some((x, y));
 
             self.events.add(GameEvent::GameEnd(self.board.clone()));
-            self.state = GameState::GameOver;
+            self.finish(GameState::GameOver);
             return;
         };
+        self.board.cleared += 1;
         self.events.add(GameEvent::SweepBegin);
 
+        // Breadth first flood fill outwards from zero tiles. The numbered
+        // tiles bordering the opened region are revealed as well (their
+        // counts are already final from `generate`) but are not expanded.
+        // A tile is marked swept as it is queued, so the swept check doubles
+        // as the visited check and every tile is revealed and queued at most
+        // once.
         let mut scan_list = VecDeque::from([(x, y)]);
-        while scan_list.len() > 0 {
-            for &scan_location in SCAN.iter() {
-                let old_tile = self.board.tiles[scan_list[0].0][scan_list[0].1];
-                if old_tile.state != TileState::Zero {
-                    continue;
-                }
-                let x = scan_list[0].0 as isize + scan_location.0;
-                let y = scan_list[0].1 as isize + scan_location.1;
-
-                if x >= self.board.width as isize
-                    || y >= self.board.height as isize
-                    || x < 0
-                    || y < 0
-                {
+        while let Some((scan_x, scan_y)) = scan_list.pop_front() {
+            if self.board.get(scan_x, scan_y).map(|tile| tile.state) != Some(TileState::Zero) {
+                continue;
+            }
+            for (x, y) in self.board.neighbors(scan_x, scan_y) {
+                let Some(tile) = self.board.get_mut(x, y) else {
                     continue;
-                }
-                let y = y as usize;
-                let x = x as usize;
-                let tile = self.board.tiles[x][y];
+                };
                 if tile.swept {
                     continue;
                 }
+                tile.swept = true;
+                self.events.add(GameEvent::RevealTile(x, y, *tile));
+                self.board.cleared += 1;
+                self.board.swept += 1;
                 scan_list.push_back((x, y));
-
-                self.board.tiles[x][y].swept = true;
-                self.events
-                    .add(GameEvent::RevealTile(x, y, self.board.tiles[x][y].clone()));
             }
-            scan_list.pop_front();
         }
         self.events.add(GameEvent::SweepDone);
+
+        // Sweeping every safe tile wins as well as flagging every mine
+        if self.board.unswept_safe_count() == 0 {
+            self.events.add(GameEvent::GameEnd(self.board.clone()));
+            self.finish(GameState::Victory);
+        }
     }
-    pub fn flag(&mut self, x: usize, y: usize) {
-        if self.state != GameState::Playing || self.board.tiles[x][y].swept {
+    fn flag_tile(&mut self, x: usize, y: usize) {
+        let Some(tile) = self.movable_tile(x, y) else {
             return;
+        };
+        if tile.swept {
+            return self.reject(x, y, Rejection::Swept);
+        }
+        let flagged = tile.modifier == Some(TileModifier::Flagged);
+        if !flagged && self.strict_flags && self.board.flags == self.board.mines {
+            return self.reject(x, y, Rejection::NoFlagsLeft);
         }
 
-        let mut tile = &mut self.board.tiles[x][y];
-        if let Some(TileModifier::Flagged) = tile.modifier {
-            tile.modifier = None;
-            if tile.state == TileState::Mine {
+        let board_tile = self.board.get_mut(x, y).expect("checked by movable_tile");
+        board_tile.modifier = (!flagged).then_some(TileModifier::Flagged);
+        self.events.add(GameEvent::FlagTile(x, y, *board_tile));
+        if flagged {
+            self.board.flags -= 1;
+            if tile.is_mine() {
                 self.board.valid_flags -= 1;
             }
-            self.board.flags -= 1;
-            self.events.add(GameEvent::FlagTile(x, y, tile.clone()));
-        } else if tile.modifier.is_none() {
-            tile.modifier = Some(TileModifier::Flagged);
+        } else {
             self.board.flags += 1;
-            if tile.state == TileState::Mine {
+            if tile.is_mine() {
                 self.board.valid_flags += 1;
             }
-            self.events.add(GameEvent::FlagTile(x, y, tile.clone()));
-
             if self.board.valid_flags == self.board.mines {
                 self.events.add(GameEvent::FlagAllMines);
-                self.state = GameState::Victory;
-                return;
+                self.finish(GameState::Victory);
+            }
+        }
+    }
+    fn question_tile(&mut self, x: usize, y: usize) {
+        let Some(tile) = self.movable_tile(x, y) else {
+            return;
+        };
+        if tile.swept {
+            return self.reject(x, y, Rejection::Swept);
+        }
+        let modifier = match tile.modifier {
+            None => Some(TileModifier::Unsure),
+            Some(TileModifier::Unsure) => None,
+            Some(TileModifier::Flagged) => return self.reject(x, y, Rejection::Flagged),
+        };
+        let tile = self.board.get_mut(x, y).expect("checked by movable_tile");
+        tile.modifier = modifier;
+        self.events.add(GameEvent::QuestionTile(x, y, *tile));
+    }
+    pub fn pause(&mut self) {
+        if self.state == GameState::Playing {
+            self.stop_clock();
+            self.state = GameState::Paused;
+            self.events.add(GameEvent::Pause);
+        }
+    }
+    /// Ends the game, stopping the clock.
+    fn finish(&mut self, state: GameState) {
+        self.stop_clock();
+        self.state = state;
+    }
+    fn stop_clock(&mut self) {
+        self.played = self.elapsed();
+        self.start_time = None;
+    }
+    /// Time spent playing so far, not counting pauses. Zero before the first
+    /// sweep, and fixed at the final move once the game is over.
+    pub fn elapsed(&self) -> time::Duration {
+        self.played
+            + self
+                .start_time
+                .map_or(time::Duration::ZERO, |start_time| start_time.elapsed())
+    }
+    /// Ends the game if the time limit has run out. Returns whether it did.
+    pub fn check_timeout(&mut self) -> bool {
+        if !self.in_progress() || !self.timed_out(self.elapsed()) {
+            return false;
+        }
+        self.finish(GameState::GameOver);
+        self.events.add(GameEvent::TimeUp);
+        self.events.add(GameEvent::GameEnd(self.board.clone()));
+        true
+    }
+    fn timed_out(&self, elapsed: time::Duration) -> bool {
+        self.timeout.is_some_and(|timeout| elapsed >= timeout)
+    }
+    /// Whether the game was lost by running out of time rather than by
+    /// sweeping a mine.
+    pub fn time_up(&self) -> bool {
+        self.lost() && self.detonated.is_none()
+    }
+    pub fn resume(&mut self) {
+        if self.state == GameState::Paused {
+            self.start_time = Some(time::Instant::now());
+            self.state = GameState::Playing;
+            self.events.add(GameEvent::Resume);
+        }
+    }
+    fn chord_tile(&mut self, x: usize, y: usize) {
+        if !self.movable_tile(x, y).is_some_and(|tile| tile.swept) {
+            return;
+        }
+        let neighbors: Vec<(usize, usize)> = self.board.neighbors(x, y).collect();
+        let count = |predicate: fn(&Tile) -> bool| {
+            neighbors
+                .iter()
+                .filter_map(|&(x, y)| self.board.get(x, y))
+                .filter(|tile| predicate(tile))
+                .count()
+        };
+        let mines = count(|tile| tile.is_mine());
+        let flags = count(|tile| tile.modifier == Some(TileModifier::Flagged));
+        if mines != flags {
+            return;
+        }
+        for (x, y) in neighbors {
+            let hidden = self
+                .board
+                .get(x, y)
+                .is_some_and(|tile| !tile.swept && tile.modifier != Some(TileModifier::Flagged));
+            if hidden {
+                self.sweep_tile(x, y);
+            }
+        }
+    }
+    /// Makes one round of the obvious moves. Every numbered tile with as many
+    /// flags around it as its number is chorded, and every one with only as
+    /// many hidden neighbors as its number has them all flagged. Returns how
+    /// many tiles were swept or flagged.
+    pub fn auto_step(&mut self) -> usize {
+        let first_event = self.events.events.len();
+        for x in 0..self.board.width {
+            for y in 0..self.board.height {
+                let tile = self.board.tiles[x][y];
+                if self.state != GameState::Playing || !tile.swept {
+                    continue;
+                }
+                let Some(number) = tile.state.adjacent_mines().map(usize::from) else {
+                    continue;
+                };
+                let neighbors: Vec<(usize, usize)> = self.board.neighbors(x, y).collect();
+                let hidden = neighbors
+                    .iter()
+                    .filter(|&&(x, y)| !self.board.tiles[x][y].swept)
+                    .count();
+                let flags = neighbors
+                    .iter()
+                    .filter(|&&(x, y)| {
+                        self.board.tiles[x][y].modifier == Some(TileModifier::Flagged)
+                    })
+                    .count();
+                if hidden == flags {
+                    continue;
+                }
+                if flags == number {
+                    self.make_move(Move::Chord(x, y));
+                } else if hidden == number {
+                    for (x, y) in neighbors {
+                        let tile = self.board.tiles[x][y];
+                        if !tile.swept && tile.modifier != Some(TileModifier::Flagged) {
+                            self.make_move(Move::Flag(x, y));
+                        }
+                    }
+                }
+            }
+        }
+        self.events
+            .events
+            .range(first_event..)
+            .filter(|event| matches!(event, GameEvent::RevealTile(..) | GameEvent::FlagTile(..)))
+            .count()
+    }
+    /// Whether the rest of the board can be cleared from what has been
+    /// revealed so far without guessing. Only the numbers are gone by, as
+    /// the player's flags may be wrong. Tiles are worked out with the mine
+    /// counts around each number, comparing every pair of numbers whose
+    /// hidden neighbors overlap, and the number of mines left on the board.
+    pub fn is_solvable_from_here(&self) -> bool {
+        match self.state {
+            GameState::Playing | GameState::Paused => (),
+            GameState::Victory => return true,
+            GameState::Empty | GameState::GameOver => return false,
+        }
+        let (revealed, _) = self.deduce(true);
+        self.board
+            .iter()
+            .all(|(x, y, tile)| revealed[x][y] || tile.is_mine())
+    }
+    /// Each hidden tile's chance of having a mine, as far as the player can
+    /// tell from the numbers revealed and the mine count, indexed like
+    /// [`GameBoard::tiles`]. Tiles the numbers settle are 0 or 1, the rest
+    /// are estimates: next to a number, the largest share of mines among the
+    /// hidden tiles around any number it touches, and elsewhere an even
+    /// share of the mines not expected next to numbers. Swept tiles, and
+    /// every tile while the game isn't under way, are `None`.
+    pub fn mine_probabilities(&self) -> Vec<Vec<Option<f64>>> {
+        let board = &self.board;
+        let mut probabilities = vec![vec![None; board.height]; board.width];
+        if !matches!(self.state, GameState::Playing | GameState::Paused) {
+            return probabilities;
+        }
+        // The numbers under safe tiles aren't known until they're swept
+        let (safe, mine) = self.deduce(false);
+        let mut constraints = self.constraints(&safe, &mine, false);
+        let (rest, mines_left) = constraints.pop().expect("the whole board is a constraint");
+        for (x, y) in board.positions(|tile| !tile.swept) {
+            if mine[x][y] {
+                probabilities[x][y] = Some(1.0);
+            } else if safe[x][y] {
+                probabilities[x][y] = Some(0.0);
+            }
+        }
+        let mut next_to_numbers = BTreeSet::new();
+        for (tiles, count) in &constraints {
+            let share = *count as f64 / tiles.len() as f64;
+            for &(x, y) in tiles {
+                let probability = probabilities[x][y].get_or_insert(0.0);
+                *probability = probability.max(share);
+                next_to_numbers.insert((x, y));
+            }
+        }
+        let expected: f64 = next_to_numbers
+            .iter()
+            .map(|&(x, y)| probabilities[x][y].unwrap_or_default())
+            .sum();
+        let elsewhere: Vec<_> = rest.difference(&next_to_numbers).collect();
+        let share = (mines_left as f64 - expected).max(0.0) / elsewhere.len().max(1) as f64;
+        for &(x, y) in elsewhere {
+            probabilities[x][y] = Some(share.min(1.0));
+        }
+        probabilities
+    }
+    /// Works out which hidden tiles must be safe and which must be mines
+    /// from the numbers alone, without the player's flags as they may be
+    /// wrong. With `sweep_safe`, the numbers under safe tiles are used too,
+    /// as though they had been swept. Returns which tiles are revealed or
+    /// safe, and which are mines.
+    fn deduce(&self, sweep_safe: bool) -> (Vec<Vec<bool>>, Vec<Vec<bool>>) {
+        let board = &self.board;
+        let mut revealed: Vec<Vec<bool>> = board
+            .tiles
+            .iter()
+            .map(|column| column.iter().map(|tile| tile.swept).collect())
+            .collect();
+        let mut mine = vec![vec![false; board.height]; board.width];
+        loop {
+            let constraints = self.constraints(&revealed, &mine, sweep_safe);
+            let mut safe = BTreeSet::new();
+            let mut mines = BTreeSet::new();
+            let mut settle = |tiles: &BTreeSet<(usize, usize)>, count: usize| {
+                if count == 0 {
+                    safe.extend(tiles.iter().copied());
+                } else if count == tiles.len() {
+                    mines.extend(tiles.iter().copied());
+                }
+            };
+            for (tiles, count) in &constraints {
+                settle(tiles, *count);
+            }
+            // Where one number's hidden neighbors are all next to another
+            // number too, the difference holds the difference in mines
+            for (small, small_count) in &constraints {
+                for (large, large_count) in &constraints {
+                    if small.len() < large.len() && small.is_subset(large) {
+                        let rest = large.difference(small).copied().collect();
+                        settle(&rest, large_count - small_count);
+                    }
+                }
+            }
+            if safe.is_empty() && mines.is_empty() {
+                break;
+            }
+            for (x, y) in safe {
+                revealed[x][y] = true;
+            }
+            for (x, y) in mines {
+                mine[x][y] = true;
+            }
+        }
+        (revealed, mine)
+    }
+    /// What the numbers say about the tiles not yet known to be safe or
+    /// mines: each constraint is a set of those tiles and how many mines are
+    /// among them. The last one is every such tile and the mines left. Only
+    /// swept tiles' numbers are read, unless `sweep_safe`.
+    fn constraints(
+        &self,
+        revealed: &[Vec<bool>],
+        mine: &[Vec<bool>],
+        sweep_safe: bool,
+    ) -> Vec<(BTreeSet<(usize, usize)>, usize)> {
+        let board = &self.board;
+        let tiles = || (0..board.width).flat_map(|x| (0..board.height).map(move |y| (x, y)));
+        let unknown = |(x, y): (usize, usize)| !revealed[x][y] && !mine[x][y];
+        let shown = |(x, y): (usize, usize)| match sweep_safe {
+            true => revealed[x][y],
+            false => board.tiles[x][y].swept,
+        };
+        let mut constraints = Vec::new();
+        for (x, y) in tiles().filter(|&tile| shown(tile)) {
+            let neighbors = board.neighbors(x, y);
+            let (unknown_tiles, found): (Vec<_>, Vec<_>) = neighbors
+                .filter(|&tile| unknown(tile) || mine[tile.0][tile.1])
+                .partition(|&tile| unknown(tile));
+            let number = board.tiles[x][y].state.adjacent_mines();
+            if let (false, Some(number)) = (unknown_tiles.is_empty(), number) {
+                constraints.push((
+                    unknown_tiles.into_iter().collect(),
+                    usize::from(number) - found.len(),
+                ));
+            }
+        }
+        let found = tiles().filter(|&(x, y)| mine[x][y]).count();
+        constraints.push((
+            tiles().filter(|&tile| unknown(tile)).collect(),
+            board.mines - found,
+        ));
+        constraints
+    }
+    /// The code to share this board with, if it was seeded.
+    pub fn code(&self) -> Option<String> {
+        self.settings().to_code()
+    }
+    /// [`GameBoard::fingerprint`] as 16 hex digits, for showing to players.
+    /// `None` until the first sweep places the mines.
+    pub fn fingerprint(&self) -> Option<String> {
+        (self.state != GameState::Empty).then(|| format!("{:016x}", self.board.fingerprint()))
+    }
+    /// The settings the game was made with.
+    pub fn settings(&self) -> GameSettings {
+        GameSettings {
+            width: self.board.width,
+            height: self.board.height,
+            mines: self.board.mines,
+            seed: self.seed,
+        }
+    }
+    /// Calls `listener` with every event from now on, as soon as it happens
+    /// and in the same order as [`Events::next`] hands them out. Events are
+    /// still queued for `next` as well.
+    ///
+    /// ```
+    /// use minesweeper_cli::prelude::*;
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// let mut game = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+    /// let revealed = Rc::new(Cell::new(0));
+    /// let counter = revealed.clone();
+    /// game.subscribe(move |event| {
+    ///     if let GameEvent::RevealTile(..) = event {
+    ///         counter.set(counter.get() + 1);
+    ///     }
+    /// });
+    /// game.sweep(0, 0).unwrap();
+    /// assert_eq!(revealed.get(), 2);
+    /// ```
+    pub fn subscribe(&mut self, listener: impl FnMut(&GameEvent) + 'static) {
+        self.events.listeners.push(Box::new(listener));
+    }
+    /// True once the first tile is swept, until the game is won or lost.
+    pub fn in_progress(&self) -> bool {
+        self.state == GameState::Playing || self.state == GameState::Paused
+    }
+    /// Whether the clock is counting: the game is being played and, on a
+    /// replayed board, a move has been made.
+    pub fn clock_running(&self) -> bool {
+        self.start_time.is_some()
+    }
+    /// Whether moves are being accepted: started, and neither paused nor over.
+    pub fn is_playing(&self) -> bool {
+        self.state == GameState::Playing
+    }
+    pub fn is_over(&self) -> bool {
+        self.state.is_terminal()
+    }
+    pub fn won(&self) -> bool {
+        self.state == GameState::Victory
+    }
+    pub fn lost(&self) -> bool {
+        self.state == GameState::GameOver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lost_life_flags_the_mine() {
+        let mut game = Minesweeper::from_layout(3, 1, &[(0, 0), (2, 0)]).unwrap();
+        game.lives = 2;
+        game.sweep(0, 0).unwrap();
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.lives, 1);
+        assert!(game.board.tiles[0][0].modifier == Some(TileModifier::Flagged));
+        assert_eq!(game.board.flags, 1);
+        let outcome = &game.history.back().unwrap().outcome;
+        assert_eq!(outcome, "hit a mine and lost a life");
+    }
+
+    #[test]
+    fn lost_life_flags_the_mine_with_no_flags_left() {
+        let mut game = Minesweeper::from_layout(3, 1, &[(0, 0), (2, 0)]).unwrap();
+        game.lives = 2;
+        game.strict_flags = true;
+        game.flag(1, 0).unwrap();
+        game.flag(2, 0).unwrap();
+        game.sweep(0, 0).unwrap();
+        assert!(game.board.tiles[0][0].modifier == Some(TileModifier::Flagged));
+        assert_eq!(game.state, GameState::Playing);
+        let outcome = &game.history.back().unwrap().outcome;
+        assert_eq!(outcome, "hit a mine and lost a life");
+    }
+
+    #[test]
+    fn tiles_go_row_by_row() {
+        let mut game = Minesweeper::from_layout(3, 2, &[(0, 0)]).unwrap();
+        let rows = [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)];
+        let order: Vec<_> = game.board.iter().map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(order, rows);
+        let order: Vec<_> = game.board.iter_mut().map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(order, rows);
+        game.flag(1, 1).unwrap();
+        game.flag(2, 0).unwrap();
+        assert_eq!(game.board.flag_positions(), [(2, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn iter_mut_changes_the_tile_it_names() {
+        let mut game = Minesweeper::from_layout(3, 2, &[(0, 0)]).unwrap();
+        for (x, y, tile) in game.board.iter_mut() {
+            tile.swept = (x, y) == (2, 1);
+        }
+        assert!(game.board.get(2, 1).unwrap().swept);
+        assert_eq!(
+            game.board.iter().filter(|(_, _, tile)| tile.swept).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn neighbors_stop_at_the_edges() {
+        let game = Minesweeper::from_layout(3, 3, &[(0, 0)]).unwrap();
+        assert_eq!(game.board.neighbors(0, 0).count(), 3);
+        assert_eq!(game.board.neighbors(1, 0).count(), 5);
+        assert_eq!(game.board.neighbors(1, 1).count(), 8);
+        assert_eq!(game.board.neighbors(2, 2).count(), 3);
+    }
+
+    #[test]
+    fn moves_off_the_board_are_errors() {
+        let mut game = Minesweeper::from_layout(10, 10, &[(0, 0)]).unwrap();
+        let off_board = |x, y| Err(MinesweeperError::OutOfBounds { x, y });
+        assert_eq!(game.sweep(10, 0), off_board(10, 0));
+        assert_eq!(game.sweep(99, 99), off_board(99, 99));
+        assert_eq!(game.flag(0, 10), off_board(0, 10));
+        assert_eq!(game.flag(usize::MAX, 0), off_board(usize::MAX, 0));
+        assert_eq!(game.board.swept_count(), 0);
+        assert_eq!(game.board.flags, 0);
+    }
+
+    #[test]
+    fn moves_on_the_board_go_ahead() {
+        let mut game = Minesweeper::from_layout(10, 10, &[(0, 0)]).unwrap();
+        assert_eq!(game.flag(0, 0), Ok(()));
+        assert_eq!(game.sweep(9, 9), Ok(()));
+        assert_eq!(game.state, GameState::Victory);
+    }
+
+    #[test]
+    fn moves_off_the_board_are_not_recorded() {
+        let mut game = Minesweeper::from_layout(3, 3, &[(0, 0)]).unwrap();
+        let off_board = Err(MinesweeperError::OutOfBounds { x: 3, y: 1 });
+        assert_eq!(game.question(3, 1), off_board);
+        assert_eq!(game.chord(3, 1), off_board);
+        assert_eq!(game.moves, 0);
+        assert!(game.history.is_empty());
+        assert!(game.events.next().is_none());
+    }
+
+    #[test]
+    fn counts_round_trip() {
+        for count in 0..=8 {
+            assert_eq!(TileState::from_count(count).adjacent_mines(), Some(count));
+        }
+        assert_eq!(TileState::Mine.adjacent_mines(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "a tile can't have 9 mines around it")]
+    fn nine_mines_are_too_many() {
+        TileState::from_count(9);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn boards_round_trip_through_json() {
+        let mut game = Minesweeper::from_layout(4, 3, &[(0, 0), (3, 2)]).unwrap();
+        game.sweep(2, 0).unwrap();
+        game.flag(0, 0).unwrap();
+        game.question(3, 2).unwrap();
+        let json = serde_json::to_string(&game.board).unwrap();
+        let board: GameBoard = serde_json::from_str(&json).unwrap();
+        assert_eq!(board.to_ascii_grid(false), game.board.to_ascii_grid(false));
+        assert_eq!(board.to_ascii_grid(true), game.board.to_ascii_grid(true));
+        assert_eq!(board.fingerprint(), game.board.fingerprint());
+        assert_eq!(board.flags, 1);
+        assert_eq!(board.swept_count(), game.board.swept_count());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn inconsistent_boards_are_rejected() {
+        let mut game = Minesweeper::from_layout(4, 3, &[(0, 0), (3, 2)]).unwrap();
+        game.sweep(2, 0).unwrap();
+        let saved = serde_json::to_value(&game.board).unwrap();
+        let read = |change: &dyn Fn(&mut serde_json::Value)| {
+            let mut json = saved.clone();
+            change(&mut json);
+            serde_json::from_value::<GameBoard>(json)
+        };
+        assert!(read(&|json| json["width"] = 1.into()).is_err());
+        assert!(read(&|json| json["height"] = 4.into()).is_err());
+        assert!(read(&|json| json["mines"] = 3.into()).is_err());
+        assert!(read(&|json| json["mines"] = 12.into()).is_err());
+        assert!(read(&|json| {
+            json["tiles"][0].as_array_mut().unwrap().pop();
+        })
+        .is_err());
+
+        let board = read(&|json| {
+            json["flags"] = 5.into();
+            json["valid_flags"] = 5.into();
+            json["cleared"] = 10.into();
+            json["swept"] = 0.into();
+        })
+        .unwrap();
+        assert_eq!(board.flags, 0);
+        assert_eq!(board.swept_count(), game.board.swept_count());
+        assert_eq!(board.unswept_safe_count(), game.board.unswept_safe_count());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn settings_round_trip_through_json() {
+        let settings = GameSettings {
+            seed: Some(7),
+            ..GameSettings::expert()
+        };
+        let json = serde_json::to_string(&settings).unwrap();
+        let read: GameSettings = serde_json::from_str(&json).unwrap();
+        let fields = |settings: &GameSettings| {
+            (
+                settings.width,
+                settings.height,
+                settings.mines,
+                settings.seed,
+            )
+        };
+        assert_eq!(fields(&read), fields(&settings));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn tile_states_are_written_as_counts() {
+        for count in 0..=8 {
+            let state = TileState::from_count(count);
+            let json = serde_json::to_string(&state).unwrap();
+            assert_eq!(json, count.to_string());
+            let read: TileState = serde_json::from_str(&json).unwrap();
+            assert!(read == state);
+        }
+        assert_eq!(serde_json::to_string(&TileState::Mine).unwrap(), "\"mine\"");
+        assert!(serde_json::from_str::<TileState>("9").is_err());
+    }
+
+    /// Plays a game with two lives until it's lost, leaving something in
+    /// every part of the game which a reset has to clear.
+    fn lost_game() -> Minesweeper {
+        let mut game = Minesweeper::from_layout(4, 1, &[(0, 0), (3, 0)]).unwrap();
+        game.lives = 2;
+        game.question(2, 0).unwrap();
+        game.sweep(0, 0).unwrap();
+        game.sweep(3, 0).unwrap();
+        assert!(game.lost());
+        game
+    }
+
+    fn assert_fresh(game: &mut Minesweeper) {
+        assert_eq!(game.board.flags, 0);
+        assert_eq!(game.board.swept_count(), 0);
+        assert_eq!(game.board.cleared_fraction(), 0.0);
+        assert!(game
+            .board
+            .iter()
+            .all(|(_, _, tile)| tile.modifier.is_none()));
+        assert_eq!(game.lives, 2);
+        assert_eq!(game.moves, 0);
+        assert!(game.history.is_empty());
+        assert!(game.detonated.is_none());
+        assert!(game.events.next().is_none());
+    }
+
+    #[test]
+    fn reset_leaves_nothing_of_the_last_game() {
+        let mut game = lost_game();
+        game.reset(Some(1));
+        assert_eq!(game.state, GameState::Empty);
+        assert!(game.board.iter().all(|(_, _, tile)| !tile.is_mine()));
+        assert_fresh(&mut game);
+    }
+
+    #[test]
+    fn reset_keep_mines_leaves_only_the_mines() {
+        let mut game = lost_game();
+        let fingerprint = game.board.fingerprint();
+        game.reset_keep_mines();
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.board.fingerprint(), fingerprint);
+        assert_fresh(&mut game);
+        assert!(!game.clock_running());
+        game.sweep(1, 0).unwrap();
+        assert!(game.clock_running());
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn replayed_boards_keep_the_clock_at_zero_until_a_move() {
+        let mut game = lost_game();
+        game.reset_keep_mines();
+        game.timeout = Some(time::Duration::from_millis(10));
+        std::thread::sleep(time::Duration::from_millis(20));
+        assert_eq!(game.elapsed(), time::Duration::ZERO);
+        assert!(!game.check_timeout());
+        game.timeout = None;
+        game.flag(1, 0).unwrap();
+        std::thread::sleep(time::Duration::from_millis(20));
+        assert!(game.elapsed() > time::Duration::ZERO);
+    }
+
+    #[test]
+    fn codes_for_boards_over_the_default_limit_read_back() {
+        let settings = GameSettings {
+            width: 2000,
+            height: 1000,
+            mines: 400_000,
+            seed: Some(3),
+        };
+        let read = GameSettings::from_code(&settings.to_code().unwrap()).unwrap();
+        assert_eq!((read.width, read.height, read.mines), (2000, 1000, 400_000));
+        assert!(read.validate().is_err());
+        assert!(read.validate_up_to(2_000_000).is_ok());
+    }
+
+    #[test]
+    fn neighbors_leave_out_the_tile_itself() {
+        let game = Minesweeper::from_layout(4, 1, &[(0, 0)]).unwrap();
+        let neighbors = |x| game.board.neighbors(x, 0).collect::<Vec<_>>();
+        assert_eq!(neighbors(0), [(1, 0)]);
+        assert_eq!(neighbors(2), [(1, 0), (3, 0)]);
+        let game = Minesweeper::from_layout(1, 2, &[(0, 0)]).unwrap();
+        assert_eq!(game.board.neighbors(0, 0).collect::<Vec<_>>(), [(0, 1)]);
+    }
+
+    #[test]
+    fn flood_fill_reveals_each_tile_once() {
+        let mut game = Minesweeper::from_layout(8, 6, &[(0, 0)]).unwrap();
+        game.sweep(7, 5).unwrap();
+        let mut revealed = Vec::new();
+        while let Some(event) = game.events.next() {
+            if let GameEvent::RevealTile(x, y, _) = event {
+                revealed.push((x, y));
+            }
+        }
+        assert_eq!(revealed.len(), 8 * 6 - 1);
+        revealed.sort();
+        revealed.dedup();
+        assert_eq!(revealed.len(), 8 * 6 - 1);
+        assert!(!revealed.contains(&(0, 0)));
+        assert_eq!(game.board.swept_count(), 8 * 6 - 1);
+        assert!(game.won());
+    }
+
+    #[test]
+    fn flood_fill_shows_the_numbers_around_it() {
+        // Sweeping the left side opens the first three columns, and shows the
+        // ones next to the mine
+        let mut game = Minesweeper::from_layout(5, 3, &[(4, 1)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        let tile = |x, y| *game.board.get(x, y).unwrap();
+        for y in 0..3 {
+            assert!(tile(2, y).swept);
+            assert_eq!(tile(2, y).state.adjacent_mines(), Some(0));
+            assert!(tile(3, y).swept);
+            assert_eq!(tile(3, y).state.adjacent_mines(), Some(1));
+        }
+        // Past the numbers nothing opens, even where there's no mine
+        assert!(!tile(4, 0).swept);
+        assert!(!tile(4, 2).swept);
+        assert!(game.in_progress());
+    }
+
+    #[test]
+    fn over_mined_settings_are_refused() {
+        let settings = |mines| GameSettings {
+            width: 3,
+            height: 3,
+            mines,
+            seed: None,
+        };
+        assert_eq!(settings(8).validate(), Ok(()));
+        assert_eq!(
+            settings(9).validate(),
+            Err(MinesweeperError::TooManyMines {
+                max: 8,
+                requested: 9
+            })
+        );
+        let built = GameSettings::builder().width(3).height(3).mines(9).build();
+        assert!(built.is_err());
+    }
+
+    #[test]
+    fn paused_games_refuse_moves_until_resumed() {
+        let mut game = Minesweeper::from_layout(3, 3, &[(0, 0)]).unwrap();
+        game.pause();
+        assert_eq!(game.state, GameState::Paused);
+        while game.events.next().is_some() {}
+        game.sweep(2, 2).unwrap();
+        game.flag(0, 0).unwrap();
+        assert_eq!(game.board.swept_count(), 0);
+        assert_eq!(game.board.flags, 0);
+        assert!(matches!(
+            game.events.next(),
+            Some(GameEvent::Rejected {
+                reason: Rejection::Paused,
+                ..
+            })
+        ));
+
+        game.resume();
+        assert_eq!(game.state, GameState::Playing);
+        game.flag(0, 0).unwrap();
+        assert_eq!(game.board.flags, 1);
+    }
+
+    #[test]
+    fn the_first_sweep_is_a_zero() {
+        for seed in 0..20 {
+            let settings = GameSettings {
+                seed: Some(seed),
+                ..GameSettings::expert()
+            };
+            let mut game = Minesweeper::new(&settings).unwrap();
+            let (x, y) = (seed as usize % 30, seed as usize % 16);
+            game.sweep(x, y).unwrap();
+            assert!(game.board.get(x, y).unwrap().state == TileState::Zero);
+            assert!(game.board.neighbors(x, y).all(|(x, y)| {
+                let tile = game.board.get(x, y).unwrap();
+                tile.swept && !tile.is_mine()
+            }));
+        }
+    }
+
+    #[test]
+    fn tiny_boards_can_be_played() {
+        for seed in 0..10 {
+            let settings = GameSettings {
+                width: 2,
+                height: 2,
+                mines: 1,
+                seed: Some(seed),
+            };
+            let mut game = Minesweeper::new(&settings).unwrap();
+            game.sweep(0, 0).unwrap();
+            assert!(game.in_progress());
+            for (x, y) in [(1, 0), (0, 1), (1, 1)] {
+                if !game.board.get(x, y).unwrap().is_mine() {
+                    game.sweep(x, y).unwrap();
+                }
+            }
+            assert!(game.won());
+        }
+    }
+
+    #[test]
+    fn history_keeps_the_moves_in_order() {
+        let mut game = Minesweeper::from_layout(7, 1, &[(2, 0), (4, 0)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        game.sweep(6, 0).unwrap();
+        game.flag(2, 0).unwrap();
+        let history: Vec<&HistoryEntry> = game.history.iter().collect();
+        assert_eq!(history.len(), 3);
+        assert!(matches!(history[0].action, Move::Sweep(0, 0)));
+        assert!(matches!(history[1].action, Move::Sweep(6, 0)));
+        assert!(matches!(history[2].action, Move::Flag(2, 0)));
+        let outcomes: Vec<&str> = history.iter().map(|entry| &*entry.outcome).collect();
+        assert_eq!(
+            outcomes,
+            ["revealed 2 tiles", "revealed 2 tiles", "flagged"]
+        );
+    }
+
+    #[test]
+    fn states_display_as_plain_text() {
+        assert_eq!(TileState::Mine.to_string(), "mine");
+        for count in 0..=8 {
+            assert_eq!(TileState::from_count(count).to_string(), count.to_string());
+        }
+        assert_eq!(GameState::Empty.to_string(), "empty");
+        assert_eq!(GameState::Playing.to_string(), "playing");
+        assert_eq!(GameState::Paused.to_string(), "paused");
+        assert_eq!(GameState::GameOver.to_string(), "game over");
+        assert_eq!(GameState::Victory.to_string(), "victory");
+    }
+
+    #[test]
+    fn codes_read_back_as_the_same_settings() {
+        let settings = GameSettings {
+            seed: Some(123_456_789),
+            ..GameSettings::expert()
+        };
+        let code = settings.to_code().unwrap();
+        for code in [code.clone(), code.to_lowercase(), format!(" {} ", code)] {
+            let read = GameSettings::from_code(&code).unwrap();
+            assert_eq!(read.width, 30);
+            assert_eq!(read.height, 16);
+            assert_eq!(read.mines, 99);
+            assert_eq!(read.seed, Some(123_456_789));
+        }
+        assert!(GameSettings::expert().to_code().is_none());
+    }
+
+    #[test]
+    fn mistyped_codes_are_refused() {
+        let code = GameSettings {
+            seed: Some(42),
+            ..GameSettings::beginner()
+        }
+        .to_code()
+        .unwrap();
+        assert_eq!(
+            GameSettings::from_code(&format!("{}!", code)).unwrap_err(),
+            MinesweeperError::InvalidCodeCharacter('!')
+        );
+        let mut changed: Vec<char> = code.chars().collect();
+        changed[1] = if changed[1] == 'A' { 'B' } else { 'A' };
+        let changed: String = changed.into_iter().collect();
+        for code in [&code[..code.len() - 2], &changed, ""] {
+            assert_eq!(
+                GameSettings::from_code(code).unwrap_err(),
+                MinesweeperError::MalformedCode,
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn strict_flags_stop_at_the_mine_count() {
+        let mut game = Minesweeper::from_layout(4, 4, &[(0, 0), (3, 3)]).unwrap();
+        game.strict_flags = true;
+        game.flag(1, 1).unwrap();
+        game.flag(2, 2).unwrap();
+        while game.events.next().is_some() {}
+        game.flag(0, 0).unwrap();
+        assert_eq!(game.board.flags, 2);
+        assert!(game.board.get(0, 0).unwrap().modifier.is_none());
+        assert!(matches!(
+            game.events.next(),
+            Some(GameEvent::Rejected {
+                x: 0,
+                y: 0,
+                reason: Rejection::NoFlagsLeft
+            })
+        ));
+        // Taking a flag off makes room for another
+        game.flag(2, 2).unwrap();
+        game.flag(0, 0).unwrap();
+        assert_eq!(game.board.flags, 2);
+        assert_eq!(game.board.mines_left(), 0);
+    }
+
+    #[test]
+    fn safe_tiles_left_count_down_as_tiles_are_swept() {
+        let mut game = Minesweeper::from_layout(5, 3, &[(4, 1)]).unwrap();
+        assert_eq!(game.board.unswept_safe_count(), 14);
+        // Flagging doesn't sweep, even a tile without a mine
+        game.flag(4, 0).unwrap();
+        assert_eq!(game.board.unswept_safe_count(), 14);
+        game.sweep(0, 0).unwrap();
+        assert_eq!(game.board.unswept_safe_count(), 2);
+        game.sweep(4, 2).unwrap();
+        assert_eq!(game.board.unswept_safe_count(), 1);
+        assert!(game.in_progress());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn old_counter_names_still_answer() {
+        let mut game = Minesweeper::from_layout(5, 3, &[(4, 1)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        assert_eq!(game.board.remaining_safe_tiles(), 2);
+        assert_eq!(
+            game.board.cleared_percent(),
+            game.board.cleared_fraction() * 100.0
+        );
+    }
+
+    #[test]
+    fn moves_with_no_effect_say_why() {
+        let mut game = Minesweeper::from_layout(3, 3, &[(2, 2)]).unwrap();
+        game.sweep(1, 1).unwrap();
+        game.flag(0, 2).unwrap();
+        while game.events.next().is_some() {}
+
+        let rejection = |game: &mut Minesweeper| match game.events.next() {
+            Some(GameEvent::Rejected { x, y, reason }) => Some((x, y, reason)),
+            _ => None,
+        };
+        game.flag(1, 1).unwrap();
+        assert_eq!(rejection(&mut game), Some((1, 1, Rejection::Swept)));
+        game.sweep(0, 2).unwrap();
+        assert_eq!(rejection(&mut game), Some((0, 2, Rejection::Flagged)));
+        game.sweep(1, 1).unwrap();
+        assert_eq!(rejection(&mut game), Some((1, 1, Rejection::Swept)));
+        assert_eq!(game.board.swept_count(), 1);
+    }
+
+    #[test]
+    fn auto_step_flags_the_only_place_a_mine_can_be() {
+        let mut game = Minesweeper::from_layout(5, 1, &[(2, 0), (4, 0)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        // The 1 at 2,1 has one hidden neighbor, which has to be the mine
+        assert_eq!(game.auto_step(), 1);
+        assert!(game.board.get(2, 0).unwrap().modifier == Some(TileModifier::Flagged));
+        assert_eq!(game.auto_step(), 0);
+        assert!(!game.board.get(3, 0).unwrap().swept);
+    }
+
+    #[test]
+    fn auto_step_sweeps_around_a_finished_number() {
+        let mut game = Minesweeper::from_layout(3, 3, &[(0, 0), (2, 2)]).unwrap();
+        game.sweep(1, 0).unwrap();
+        game.flag(0, 0).unwrap();
+        // The 1 at 2,1 has its mine flagged, so its other neighbors are safe
+        assert!(game.auto_step() > 0);
+        for (x, y) in [(2, 0), (0, 1), (1, 1), (2, 1)] {
+            assert!(game.board.get(x, y).unwrap().swept, "{},{}", x, y);
+        }
+        assert!(game.in_progress());
+    }
+
+    #[test]
+    fn safe_zone_stops_at_the_edges() {
+        let game = Minesweeper::new(&GameSettings::beginner()).unwrap();
+        let mut zone = game.board.safe_zone(0, 0);
+        zone.sort();
+        assert_eq!(zone, [(0, 0), (0, 1), (1, 0), (1, 1)]);
+        assert_eq!(game.board.safe_zone(4, 0).len(), 6);
+        assert_eq!(game.board.safe_zone(4, 4).len(), 9);
+        assert_eq!(game.board.safe_zone(8, 8).len(), 4);
+        // Looking doesn't place the mines
+        assert_eq!(game.state, GameState::Empty);
+
+        // With little room, only some of the neighbors are kept clear
+        let settings = GameSettings {
+            width: 2,
+            height: 2,
+            mines: 1,
+            seed: None,
+        };
+        let game = Minesweeper::new(&settings).unwrap();
+        assert_eq!(game.board.safe_zone(0, 0).len(), 3);
+    }
+
+    #[test]
+    fn ascii_grid_draws_one_character_per_tile() {
+        let mut game = Minesweeper::from_layout(4, 3, &[(3, 0), (3, 2)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        game.flag(3, 0).unwrap();
+        game.question(3, 1).unwrap();
+        assert_eq!(game.board.to_ascii_grid(false), "  1F\n  2?\n  1#");
+        assert_eq!(game.board.to_ascii_grid(true), "  1*\n  2?\n  1*");
+    }
+
+    #[test]
+    fn games_end_once_the_time_is_up() {
+        let mut game = Minesweeper::from_layout(3, 3, &[(0, 0)]).unwrap();
+        game.timeout = Some(time::Duration::from_secs(60));
+        assert!(!game.timed_out(time::Duration::from_secs(59)));
+        assert!(game.timed_out(time::Duration::from_secs(60)));
+        assert!(game.timed_out(time::Duration::from_secs(61)));
+        assert!(!game.check_timeout());
+        assert!(game.in_progress());
+
+        game.timeout = Some(time::Duration::ZERO);
+        while game.events.next().is_some() {}
+        assert!(game.check_timeout());
+        assert_eq!(game.state, GameState::GameOver);
+        assert!(game.time_up());
+        assert!(matches!(game.events.next(), Some(GameEvent::TimeUp)));
+        // Only once
+        assert!(!game.check_timeout());
+    }
+
+    #[test]
+    fn flag_positions_match_the_flag_count() {
+        let mut game = Minesweeper::from_layout(4, 4, &[(0, 0), (3, 3)]).unwrap();
+        game.flag(2, 1).unwrap();
+        game.flag(0, 0).unwrap();
+        game.flag(1, 3).unwrap();
+        let flags = game.board.flag_positions();
+        assert_eq!(flags.len(), game.board.flags);
+        assert_eq!(flags, [(0, 0), (2, 1), (1, 3)]);
+    }
+
+    #[cfg(feature = "debug-introspection")]
+    #[test]
+    fn mine_positions_match_the_mine_count() {
+        let mut game = Minesweeper::new(&GameSettings {
+            seed: Some(5),
+            ..GameSettings::intermediate()
+        })
+        .unwrap();
+        assert!(game.board.mine_positions().is_empty());
+        game.sweep(8, 8).unwrap();
+        let mines = game.board.mine_positions();
+        assert_eq!(mines.len(), game.board.mines);
+        assert!(mines
+            .iter()
+            .all(|&(x, y)| game.board.get(x, y).unwrap().is_mine()));
+    }
+
+    #[test]
+    fn a_fifty_fifty_needs_a_guess() {
+        // Both 1s touch the same two hidden tiles, either could be the mine
+        let mut game = Minesweeper::from_layout(3, 2, &[(2, 0)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        assert_eq!(game.board.to_ascii_grid(false), " 1#\n 1#");
+        assert!(!game.is_solvable_from_here());
+        // Moving the mine to the bottom looks no different
+        let mut game = Minesweeper::from_layout(3, 2, &[(2, 1)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        assert!(!game.is_solvable_from_here());
+    }
+
+    #[test]
+    fn numbers_can_leave_nothing_to_guess() {
+        // The 2 at 2,1 has two hidden neighbors, so both are mines, and the 1
+        // at 2,3 already touches one of them
+        let mut game = Minesweeper::from_layout(3, 3, &[(2, 0), (2, 1)]).unwrap();
+        game.sweep(0, 2).unwrap();
+        assert_eq!(game.board.to_ascii_grid(false), " 2#\n 2#\n 1#");
+        assert!(game.is_solvable_from_here());
+    }
+
+    #[test]
+    fn the_first_sweep_can_hit_a_mine_when_allowed() {
+        let settings = GameSettings {
+            seed: Some(8),
+            ..GameSettings::beginner()
+        };
+        // This seed puts a mine in the middle
+        let mut game = Minesweeper::new(&settings).unwrap();
+        game.allow_first_mine = true;
+        game.sweep(4, 4).unwrap();
+        assert!(game.lost());
+        assert_eq!(game.detonated, Some((4, 4)));
+
+        let mut game = Minesweeper::new(&settings).unwrap();
+        game.sweep(4, 4).unwrap();
+        assert!(game.in_progress());
+    }
+
+    #[test]
+    fn density_is_the_share_of_tiles_with_a_mine() {
+        let settings = GameSettings {
+            width: 10,
+            height: 10,
+            mines: 20,
+            seed: None,
+        };
+        let game = Minesweeper::new(&settings).unwrap();
+        assert_eq!(game.board.density(), 0.2);
+        let game = Minesweeper::from_layout(4, 1, &[(0, 0)]).unwrap();
+        assert_eq!(game.board.density(), 0.25);
+    }
+
+    #[test]
+    fn classic_settings_have_the_classic_sizes() {
+        let size = |settings: GameSettings| {
+            assert_eq!(settings.seed, None);
+            (settings.width, settings.height, settings.mines)
+        };
+        assert_eq!(size(GameSettings::beginner()), (9, 9, 10));
+        assert_eq!(size(GameSettings::intermediate()), (16, 16, 40));
+        assert_eq!(size(GameSettings::expert()), (30, 16, 99));
+        const EXPERT: GameSettings = GameSettings::expert();
+        assert_eq!(EXPERT.validate(), Ok(()));
+    }
+
+    #[test]
+    fn layouts_place_exactly_the_mines_given() {
+        let game = Minesweeper::from_layout(3, 2, &[(0, 0), (2, 1)]).unwrap();
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.board.mines, 2);
+        assert_eq!(game.board.to_ascii_grid(true), "*##\n##*");
+        assert!(game.board.get(1, 0).unwrap().state == TileState::Two);
+        assert!(game.board.get(2, 0).unwrap().state == TileState::One);
+    }
+
+    #[test]
+    fn bad_layouts_are_refused() {
+        let layout = |mines: &[(usize, usize)]| Minesweeper::from_layout(3, 2, mines).err();
+        assert_eq!(
+            layout(&[(0, 0), (0, 0)]),
+            Some(MinesweeperError::DuplicateMine { x: 0, y: 0 })
+        );
+        assert_eq!(
+            layout(&[(3, 0)]),
+            Some(MinesweeperError::OutOfBounds { x: 3, y: 0 })
+        );
+        assert!(matches!(
+            layout(&[(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]),
+            Some(MinesweeperError::TooManyMines { .. })
+        ));
+    }
+
+    #[test]
+    fn boards_one_tile_wide_sweep_like_any_other() {
+        let mut game = Minesweeper::from_layout(1, 5, &[(0, 3)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        assert_eq!(game.board.to_ascii_grid(false), " \n \n1\n#\n#");
+        game.sweep(0, 4).unwrap();
+        assert!(game.won());
+        assert_eq!(game.board.to_ascii_grid(true), " \n \n1\n*\n1");
+
+        let mut game = Minesweeper::from_layout(5, 1, &[(3, 0)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        assert_eq!(game.board.to_ascii_grid(false), "  1##");
+
+        let settings = GameSettings {
+            width: 1,
+            height: 5,
+            mines: 1,
+            seed: Some(1),
+        };
+        let mut game = Minesweeper::new(&settings).unwrap();
+        game.sweep(0, 2).unwrap();
+        assert!(!game.lost());
+    }
+
+    #[test]
+    fn events_come_out_in_the_order_they_happened() {
+        let mut game = Minesweeper::from_layout(4, 1, &[(3, 0)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = game.events.next() {
+            events.push(match event {
+                GameEvent::RevealTile(x, y, _) => format!("reveal {},{}", x, y),
+                GameEvent::SweepBegin => "sweep begin".to_string(),
+                GameEvent::SweepDone => "sweep done".to_string(),
+                GameEvent::GameEnd(_) => "game end".to_string(),
+                _ => "other".to_string(),
+            });
+        }
+        assert_eq!(
+            events,
+            [
+                "reveal 0,0",
+                "sweep begin",
+                "reveal 1,0",
+                "reveal 2,0",
+                "sweep done",
+                "game end"
+            ]
+        );
+    }
+
+    #[test]
+    fn listeners_hear_each_event_as_it_happens() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut game = Minesweeper::from_layout(4, 1, &[(3, 0)]).unwrap();
+        let heard = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&heard);
+        game.subscribe(move |event| {
+            if let GameEvent::RevealTile(x, _, _) = *event {
+                log.borrow_mut().push(x);
+            }
+        });
+        game.sweep(1, 0).unwrap();
+        assert_eq!(*heard.borrow(), [1, 0, 2]);
+        // Polling still sees the same events
+        let mut polled = 0;
+        while let Some(event) = game.events.next() {
+            polled += matches!(event, GameEvent::RevealTile(..)) as usize;
+        }
+        assert_eq!(polled, 3);
+    }
+
+    #[test]
+    fn fixed_layouts_ignore_where_the_first_sweep_is() {
+        let mines_after_sweeping = |x, y| {
+            let settings = GameSettings {
+                seed: Some(11),
+                ..GameSettings::beginner()
+            };
+            let mut game = Minesweeper::new(&settings).unwrap();
+            game.fixed_layout = true;
+            game.sweep(x, y).unwrap();
+            assert!(!game.lost());
+            let mines: Vec<(usize, usize)> = game
+                .board
+                .iter()
+                .filter(|(_, _, tile)| tile.is_mine())
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            mines
+        };
+        let mines = mines_after_sweeping(0, 0);
+        let safe: Vec<(usize, usize)> = (0..9)
+            .flat_map(|y| (0..9).map(move |x| (x, y)))
+            .filter(|tile| !mines.contains(tile))
+            .collect();
+        for &(x, y) in &safe[..5] {
+            assert_eq!(mines_after_sweeping(x, y), mines);
+        }
+
+        // A mine under the first sweep moves to the first free tile
+        let (x, y) = mines[0];
+        let mut moved = mines_after_sweeping(x, y);
+        moved.sort_by_key(|&(x, y)| (y, x));
+        let mut expected: Vec<(usize, usize)> = mines[1..].to_vec();
+        expected.push(safe[0]);
+        expected.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(moved, expected);
+    }
+
+    #[test]
+    fn counters_match_a_recount_throughout_a_game() {
+        let settings = GameSettings {
+            seed: Some(3),
+            ..GameSettings::expert()
+        };
+        let mut game = Minesweeper::new(&settings).unwrap();
+        game.lives = usize::MAX;
+        let mut rng = StdRng::seed_from_u64(3);
+        game.sweep(15, 8).unwrap();
+        for _ in 0..500 {
+            let (x, y) = (rng.gen_range(0..30), rng.gen_range(0..16));
+            match rng.gen_range(0..4) {
+                0 => game.sweep(x, y),
+                1 => game.flag(x, y),
+                2 => game.question(x, y),
+                _ => game.chord(x, y),
+            }
+            .unwrap();
+
+            let board = &game.board;
+            let count = |f: fn(&Tile) -> bool| board.iter().filter(|(_, _, tile)| f(tile)).count();
+            let flagged = |tile: &Tile| tile.modifier == Some(TileModifier::Flagged);
+            assert_eq!(board.swept_count(), count(|tile| tile.swept));
+            assert_eq!(board.flags, count(flagged));
+            assert_eq!(
+                board.valid_flags,
+                count(|tile| tile.is_mine() && tile.modifier == Some(TileModifier::Flagged))
+            );
+            assert_eq!(
+                board.unswept_safe_count(),
+                count(|tile| !tile.swept && !tile.is_mine())
+            );
+            assert_eq!(
+                board.cleared_fraction(),
+                count(|tile| tile.swept && !tile.is_mine()) as f64 / (30 * 16 - 99) as f64
+            );
+            if !game.in_progress() {
+                break;
             }
         }
     }
@@ -0,0 +1,277 @@
+use crate::cli::{format_clock, Options, TIME_LABEL};
+use clap::ValueEnum;
+use crossterm::event::DisableMouseCapture;
+use crossterm::{cursor, execute, terminal};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use std::{io, panic};
+
+/// Moves the cursor to the top left and clears the screen, unlike `ESC c`
+/// this leaves the scrollback and terminal modes alone.
+pub const CLEAR: &str = "\x1b[H\x1b[2J";
+
+/// Whether the game is shown on the alternate screen, for the panic hook.
+static ON_ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// Shows the game on the terminal's alternate screen, switching back to the
+/// main screen when dropped or on panic (see [`restore_terminal_on_panic`]).
+pub struct AlternateScreen;
+
+impl AlternateScreen {
+    pub fn enter() -> io::Result<AlternateScreen> {
+        execute!(io::stdout(), terminal::EnterAlternateScreen)?;
+        ON_ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
+        Ok(AlternateScreen)
+    }
+}
+
+impl Drop for AlternateScreen {
+    fn drop(&mut self) {
+        leave_alternate_screen();
+    }
+}
+
+fn leave_alternate_screen() {
+    if ON_ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+        let _ = execute!(io::stdout(), terminal::LeaveAlternateScreen, cursor::Show);
+    }
+}
+
+/// Puts the terminal back as it was if the game panics: off the alternate
+/// screen, out of raw mode and with the cursor showing, so the panic message
+/// can be read. Installed once, before anything changes the terminal.
+pub fn restore_terminal_on_panic() {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        leave_alternate_screen();
+        if terminal::is_raw_mode_enabled().unwrap_or(false) {
+            let _ = execute!(io::stdout(), DisableMouseCapture);
+            let _ = terminal::disable_raw_mode();
+        }
+        let _ = execute!(io::stdout(), cursor::Show);
+        hook(info);
+    }));
+}
+
+/// How the board is drawn again after each move.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Redraw {
+    /// Clear the screen and print everything again
+    #[default]
+    Full,
+    /// Rewrite only the lines which changed, usually just the rows with
+    /// tiles which changed and the status line, so big boards don't flicker
+    Diff,
+}
+
+/// Remembers the last frame drawn to the terminal, so that the next one can
+/// be drawn over it instead of clearing the screen.
+pub struct Screen {
+    redraw: Redraw,
+    /// Print every frame as is, below the previous one
+    quiet: bool,
+    /// Show the cursor again after drawing, for typing commands
+    show_cursor: bool,
+    lines: Vec<String>,
+    size: Option<(u16, u16)>,
+}
+
+impl Screen {
+    pub fn new(options: &Options) -> Screen {
+        Screen {
+            redraw: options.redraw,
+            quiet: options.quiet,
+            show_cursor: !options.cursor,
+            lines: vec![],
+            size: None,
+        }
+    }
+    /// Returns what to print to replace the previous frame with `frame`.
+    pub fn draw(&mut self, frame: &str) -> String {
+        if self.quiet {
+            return frame.to_string();
+        }
+        let lines: Vec<String> = frame.lines().map(String::from).collect();
+        let size = terminal::size().ok();
+        // The old frame can only be drawn over if it's still where it was
+        // left, so the terminal mustn't have been resized or scrolled. It
+        // can't have scrolled unless the cursor reached the bottom line.
+        let in_place = self.redraw == Redraw::Diff
+            && !self.lines.is_empty()
+            && size == self.size
+            && size.is_some_and(|(_, rows)| lines.len() + 2 < rows as usize)
+            && !scrolled(size);
+
+        // Hide the cursor while drawing so it doesn't flicker across the board
+        let mut out = String::from("\x1b[?25l");
+        if in_place {
+            for (i, line) in lines.iter().enumerate() {
+                if self.lines.get(i) != Some(line) {
+                    write!(out, "\x1b[{};1H{}\x1b[K", i + 1, line).unwrap();
+                }
+            }
+            // Leave the cursor under the frame, clearing anything typed or
+            // printed there since the last frame
+            write!(out, "\x1b[{};1H\x1b[J", lines.len() + 1).unwrap();
+        } else {
+            write!(out, "{}{}", CLEAR, frame).unwrap();
+        }
+        if self.show_cursor {
+            out.push_str("\x1b[?25h");
+        }
+        self.lines = lines;
+        self.size = size;
+        out
+    }
+    /// Whether the terminal has changed size since the last frame.
+    pub fn resized(&self) -> bool {
+        terminal::size().ok() != self.size
+    }
+    /// Updates the time on the status line of the last frame every second,
+    /// leaving the rest of the screen alone.
+    pub fn ticker(&self, start_time: Instant) -> Option<Ticker> {
+        if self.quiet || scrolled(self.size) {
+            return None;
+        }
+        self.start_ticker(start_time)
+    }
+    /// Like [`Screen::ticker`], for straight after a frame was drawn with
+    /// nothing printed since. Doesn't ask the terminal where the cursor is,
+    /// as the answer would be lost to another thread waiting for input.
+    pub fn ticker_after_draw(&self, start_time: Instant) -> Option<Ticker> {
+        let fits = self
+            .size
+            .is_some_and(|(_, rows)| self.lines.len() + 2 < rows as usize);
+        if self.quiet || !fits {
+            return None;
+        }
+        self.start_ticker(start_time)
+    }
+    fn start_ticker(&self, start_time: Instant) -> Option<Ticker> {
+        let row = self
+            .lines
+            .iter()
+            .position(|line| line.starts_with(TIME_LABEL))?
+            + 1;
+        let column = TIME_LABEL.len() + 1;
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::spawn(move || loop {
+            let until_next_second = Duration::from_secs(1)
+                - Duration::from_nanos(start_time.elapsed().subsec_nanos().into());
+            if let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(until_next_second) {
+                // Save the cursor and put it back afterwards, so that a
+                // partly typed command isn't disturbed
+                let mut stdout = io::stdout();
+                let _ = write!(
+                    stdout,
+                    "\x1b7\x1b[{};{}H{}\x1b8",
+                    row,
+                    column,
+                    format_clock(start_time.elapsed())
+                );
+                let _ = stdout.flush();
+            } else {
+                break;
+            }
+        });
+        Some(Ticker {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Whether the screen may have scrolled since the last frame, moving it
+/// away from the top. It can't have unless the cursor reached the bottom line.
+fn scrolled(size: Option<(u16, u16)>) -> bool {
+    match size {
+        Some((_, rows)) => !cursor::position().is_ok_and(|(_, row)| row + 1 < rows),
+        None => true,
+    }
+}
+
+/// Calls back whenever the terminal is resized, until dropped.
+pub struct ResizeWatcher {
+    #[cfg(all(unix, feature = "resize"))]
+    signals: signal_hook::iterator::Handle,
+    #[cfg(all(unix, feature = "resize"))]
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ResizeWatcher {
+    /// Starts calling `on_resize` on each SIGWINCH. Returns `None` if resizes
+    /// can't be watched, off Unix or without the `resize` feature.
+    #[cfg(all(unix, feature = "resize"))]
+    pub fn start(mut on_resize: impl FnMut() + Send + 'static) -> Option<ResizeWatcher> {
+        use signal_hook::consts::SIGWINCH;
+        use signal_hook::iterator::Signals;
+
+        let mut signals = Signals::new([SIGWINCH]).ok()?;
+        let handle = signals.handle();
+        let thread = thread::spawn(move || {
+            for _ in signals.forever() {
+                on_resize();
+            }
+        });
+        Some(ResizeWatcher {
+            signals: handle,
+            thread: Some(thread),
+        })
+    }
+    #[cfg(not(all(unix, feature = "resize")))]
+    pub fn start(_on_resize: impl FnMut() + Send + 'static) -> Option<ResizeWatcher> {
+        None
+    }
+}
+
+#[cfg(all(unix, feature = "resize"))]
+impl Drop for ResizeWatcher {
+    fn drop(&mut self) {
+        self.signals.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Redraws the clock until dropped, see [`Screen::ticker`].
+pub struct Ticker {
+    stop: mpsc::Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_watchers_start_and_stop() {
+        let watcher = ResizeWatcher::start(|| ());
+        assert_eq!(watcher.is_some(), cfg!(all(unix, feature = "resize")));
+        // Dropping the watcher stops its thread, or hangs if it doesn't
+    }
+
+    #[cfg(all(unix, feature = "resize"))]
+    #[test]
+    fn resize_watchers_hear_sigwinch() {
+        let (resized, on_resize) = mpsc::channel();
+        let _watcher = ResizeWatcher::start(move || {
+            let _ = resized.send(());
+        });
+        signal_hook::low_level::raise(signal_hook::consts::SIGWINCH).unwrap();
+        assert!(on_resize.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+}
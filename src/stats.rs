@@ -0,0 +1,122 @@
+use minesweeper_cli::minesweeper::{GameState, Minesweeper};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{env, fmt, fs, io};
+
+/// Totals over every game finished, kept between sessions.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Stats {
+    pub played: u32,
+    pub won: u32,
+    /// Fastest win in seconds for each kind of board, keyed by its size and
+    /// mine count as `WIDTHxHEIGHTxMINES`
+    pub best_times: BTreeMap<String, f64>,
+}
+
+impl Stats {
+    /// Reads the stats saved at `path`. A missing or unreadable file counts
+    /// as no games played.
+    pub fn load(path: &Path) -> Stats {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Counts a game once it's won or lost. Returns whether it was counted,
+    /// games which were left unfinished aren't.
+    pub fn record(&mut self, game: &Minesweeper) -> bool {
+        match game.state {
+            GameState::Victory => {
+                let board = &game.board;
+                let key = format!("{}x{}x{}", board.width, board.height, board.mines);
+                let seconds = game.elapsed().as_secs_f64();
+                let best = self.best_times.entry(key).or_insert(seconds);
+                *best = best.min(seconds);
+                self.won += 1;
+            }
+            GameState::GameOver => (),
+            _ => return false,
+        }
+        self.played += 1;
+        true
+    }
+
+    /// Share of games won, from 0 to 100.
+    pub fn win_rate(&self) -> f64 {
+        match self.played {
+            0 => 0.0,
+            played => self.won as f64 * 100.0 / played as f64,
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Games played: {}", self.played)?;
+        writeln!(f, "Games won: {} ({:.0}%)", self.won, self.win_rate())?;
+        if !self.best_times.is_empty() {
+            writeln!(f, "Best times:")?;
+        }
+        for (board, seconds) in &self.best_times {
+            writeln!(f, "  {}: {:.1}s", board, seconds)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the stats are kept: `minesweeper-cli/stats.json` in the user's data
+/// directory, if there is one.
+pub fn path() -> Option<PathBuf> {
+    let data = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(data.join("minesweeper-cli").join("stats.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wins_count_as_played_and_won() {
+        let mut stats = Stats::default();
+        let mut game = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+        game.sweep(0, 0).unwrap();
+        assert!(stats.record(&game));
+        assert_eq!((stats.played, stats.won), (1, 1));
+        assert!(stats.best_times.contains_key("3x1x1"));
+        assert_eq!(stats.win_rate(), 100.0);
+    }
+
+    #[test]
+    fn losses_count_as_played_and_unfinished_games_not_at_all() {
+        let mut stats = Stats::default();
+        let mut game = Minesweeper::from_layout(3, 1, &[(2, 0)]).unwrap();
+        assert!(!stats.record(&game));
+        game.sweep(2, 0).unwrap();
+        assert!(stats.record(&game));
+        assert_eq!((stats.played, stats.won), (1, 0));
+        assert!(stats.best_times.is_empty());
+    }
+
+    #[test]
+    fn unreadable_stats_start_from_nothing() {
+        let path = env::temp_dir().join(format!("minesweeper-stats-{}.json", std::process::id()));
+        fs::write(&path, "not json").unwrap();
+        let stats = Stats::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!((stats.played, stats.won), (0, 0));
+        let stats = Stats::load(&path);
+        assert_eq!((stats.played, stats.won), (0, 0));
+    }
+}